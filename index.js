@@ -1,4 +1,4 @@
-import wasmInit from "./pkg/david_rust_web_graphics.js";
+import wasmInit, { Renderer, Scene } from "./pkg/david_rust_web_graphics.js";
 
 var speedMult = 1.0;
 
@@ -16,10 +16,36 @@ const runWasm = async () => {
 
   // Set up Context and ImageData on the canvas
   const canvasContext = canvasElement.getContext("2d");
-  const canvasImageData = canvasContext.createImageData(
+  var canvasImageData = canvasContext.createImageData(
     canvasElement.width,
     canvasElement.height
   );
+
+  // the renderer owns buffers matching the size of the canvas element
+  const renderer = new Renderer(canvasElement.width, canvasElement.height);
+
+  // the canvas is sized by the page, so follow its displayed size with the canvas, the image data and the renderer's buffers
+  new ResizeObserver(() => {
+    const width = Math.max(1, Math.round(canvasElement.clientWidth));
+    const height = Math.max(1, Math.round(canvasElement.clientHeight));
+    if (width === canvasElement.width && height === canvasElement.height) {
+      return;
+    }
+    canvasElement.width = width;
+    canvasElement.height = height;
+    canvasImageData = canvasContext.createImageData(width, height);
+    renderer.resize(width, height);
+  }).observe(canvasElement);
+
+  // the scene is built once, each frame only moves the sphere
+  const scene = new Scene();
+  scene.set_camera_perspective(82.0, 0.1, 120.0);
+  const sphere = scene.add_ico_sphere(10.0, 0);
+  scene.set_specular(sphere, 1.0, 1.0, 1.0, 32.0);
+  scene.add_ambient_light(undefined, 0.3);
+  scene.add_directional_light(undefined, 0.8, 1.0, -1.0, 1.0);
+  // fades the sphere into the white background as it moves away
+  scene.set_linear_fog(1.0, 1.0, 1.0, 30.0, 120.0);
   
   function renderFrame(){
     var date = new Date();
@@ -27,7 +53,10 @@ const runWasm = async () => {
     
     var frameBufPtr;
 
-    frameBufPtr = rustWasm.ico_anim(seconds * speedMult);
+    const t = seconds * speedMult;
+    scene.set_position(sphere, Math.cos(t) * 30.0, 0.0, 80.0 + Math.sin(t) * 50.0);
+    scene.set_rotation_degrees(sphere, (t * 5.0) % 360.0, (t * 10.0) % 360.0, 0.0);
+    frameBufPtr = scene.render(renderer);
     
     //console.log(seconds);
 
@@ -52,6 +81,18 @@ const runWasm = async () => {
     canvasContext.putImageData(canvasImageData, 0, 0);
   };
 
+  document.getElementById("renderMode").onchange = function () {
+    renderer.set_render_mode(Number(this.value));
+  };
+
+  document.getElementById("shading").onchange = function () {
+    renderer.set_shading(Number(this.value));
+  };
+
+  document.getElementById("lineMode").onchange = function () {
+    renderer.set_line_mode(Number(this.value));
+  };
+
   setInterval(() => {
     renderFrame();
   }, 1000 / 60);
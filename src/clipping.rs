@@ -0,0 +1,2 @@
+pub mod frustum;
+pub mod rect;
@@ -0,0 +1,183 @@
+// Clipping of lines and triangles against the view frustum in homogeneous clip space.
+// Clipping happens before the perspective divide, so vertices behind the camera never get
+// divided by a zero or negative w and explode into garbage screen coordinates.
+//
+// The projection matrices map the visible volume to
+//   -w <= x <= w,  -w <= y <= w,  0 <= z <= w
+
+// vertex in homogeneous clip space along with the attributes interpolated when clipping
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub struct ClipVert {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+    pub depth: f32, // view space depth, used for the z buffer and the fog pass
+    pub shade: [f32; 3], // light reaching the vert for each color channel, 1.0 is the surface's own color
+    pub position: [f32; 3], // view space, for lighting each pixel
+    pub normal: [f32; 3], // view space, for lighting each pixel, not unit length once interpolated
+    pub uv: [f32; 2] // texture coords
+}
+impl ClipVert {
+    // linear interpolation from self (t = 0.0) to other (t = 1.0)
+    pub fn lerp(&self, other:&ClipVert, t:f32) -> Self {
+        Self {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            z: self.z + (other.z - self.z) * t,
+            w: self.w + (other.w - self.w) * t,
+            depth: self.depth + (other.depth - self.depth) * t,
+            shade: lerp_array(&self.shade, &other.shade, t),
+            position: lerp_array(&self.position, &other.position, t),
+            normal: lerp_array(&self.normal, &other.normal, t),
+            uv: lerp_array(&self.uv, &other.uv, t)
+        }
+    }
+
+    // perspective divide, only valid for vertices that survived clipping (w > 0)
+    pub fn to_ndc(&self) -> (f32, f32) {
+        (self.x / self.w, self.y / self.w)
+    }
+}
+
+fn lerp_array<const N:usize>(a:&[f32; N], b:&[f32; N], t:f32) -> [f32; N] {
+    std::array::from_fn(|index| a[index] + (b[index] - a[index]) * t)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum FrustumPlane {
+    Left,
+    Right,
+    Bottom,
+    Top,
+    Near,
+    Far
+}
+
+pub const ALL_PLANES:[FrustumPlane; 6] = [
+    FrustumPlane::Near, FrustumPlane::Far,
+    FrustumPlane::Left, FrustumPlane::Right,
+    FrustumPlane::Bottom, FrustumPlane::Top
+];
+
+impl FrustumPlane {
+    // signed distance of the vertex to the plane, positive inside the frustum
+    pub fn distance(&self, vert:&ClipVert) -> f32 {
+        match self {
+            FrustumPlane::Left   => vert.w + vert.x,
+            FrustumPlane::Right  => vert.w - vert.x,
+            FrustumPlane::Bottom => vert.w + vert.y,
+            FrustumPlane::Top    => vert.w - vert.y,
+            FrustumPlane::Near   => vert.z,
+            FrustumPlane::Far    => vert.w - vert.z
+        }
+    }
+}
+
+// parametric clipping of the segment a->b against every plane (Liang–Barsky in clip space)
+// returns the visible part of the segment or None if it is entirely outside
+pub fn clip_line(a:&ClipVert, b:&ClipVert) -> Option<(ClipVert, ClipVert)> {
+    let mut t_enter:f32 = 0.0;
+    let mut t_exit:f32  = 1.0;
+
+    for plane in ALL_PLANES.iter() {
+        let dist_a = plane.distance(a);
+        let dist_b = plane.distance(b);
+
+        if dist_a < 0.0 && dist_b < 0.0 {
+            return None; // both ends outside the same plane
+        }
+        if dist_a < 0.0 {
+            t_enter = t_enter.max(dist_a / (dist_a - dist_b));
+        } else if dist_b < 0.0 {
+            t_exit = t_exit.min(dist_a / (dist_a - dist_b));
+        }
+        if t_enter > t_exit {
+            return None;
+        }
+    }
+
+    Some((a.lerp(b, t_enter), a.lerp(b, t_exit)))
+}
+
+// Sutherland–Hodgman clipping of a convex polygon against every plane
+// returns the clipped polygon in the same winding, empty if nothing is left
+pub fn clip_polygon(verts:&[ClipVert]) -> Vec<ClipVert> {
+    let mut output:Vec<ClipVert> = verts.to_vec();
+
+    for plane in ALL_PLANES.iter() {
+        if output.len() < 3 {
+            break;
+        }
+        let input = output;
+        output = Vec::with_capacity(input.len() + 1);
+
+        let mut prev = input[input.len() - 1];
+        let mut dist_prev = plane.distance(&prev);
+        for curr in input.iter() {
+            let dist_curr = plane.distance(curr);
+            if dist_curr >= 0.0 {
+                if dist_prev < 0.0 {
+                    // entering, add the intersection then the vertex
+                    output.push(prev.lerp(curr, dist_prev / (dist_prev - dist_curr)));
+                }
+                output.push(*curr);
+            } else if dist_prev >= 0.0 {
+                // leaving, only the intersection is kept
+                output.push(prev.lerp(curr, dist_prev / (dist_prev - dist_curr)));
+            }
+            prev = *curr;
+            dist_prev = dist_curr;
+        }
+    }
+
+    if output.len() < 3 {
+        output.clear();
+    }
+    output
+}
+
+pub fn clip_triangle(a:&ClipVert, b:&ClipVert, c:&ClipVert) -> Vec<ClipVert> {
+    clip_polygon(&[*a, *b, *c])
+}
+
+#[test]
+fn clip_line_test() {
+    let vert = |x:f32, y:f32, z:f32, w:f32| ClipVert{x, y, z, w, depth:w, ..Default::default()};
+
+    // inside line is untouched
+    let a = vert(-0.5, 0.0, 0.5, 1.0);
+    let b = vert( 0.5, 0.0, 0.5, 1.0);
+    assert_eq!(clip_line(&a, &b), Some((a, b)));
+
+    // line crossing the near plane from behind the camera is cut at z = 0
+    let behind = vert(0.0, 0.0, -1.0, -0.5);
+    let front  = vert(0.0, 0.0,  1.0,  2.0);
+    let (start, end) = clip_line(&behind, &front).unwrap();
+    assert!(start.z.abs() < 0.0001 && start.w > 0.0);
+    assert_eq!(end, front);
+
+    // both ends past the right plane
+    assert_eq!(clip_line(&vert(2.0, 0.0, 0.5, 1.0), &vert(3.0, 0.5, 0.5, 1.0)), None);
+}
+
+#[test]
+fn clip_triangle_test() {
+    let vert = |x:f32, y:f32, z:f32, w:f32| ClipVert{x, y, z, w, depth:w, ..Default::default()};
+
+    // one corner behind the near plane turns the triangle into a quad
+    let clipped = clip_triangle(&vert(-0.5, 0.0, 0.5, 1.0), &vert(0.5, 0.0, 0.5, 1.0), &vert(0.0, 0.5, -0.5, 1.0));
+    assert_eq!(clipped.len(), 4);
+    for v in clipped.iter() {
+        for plane in ALL_PLANES.iter() {
+            assert!(plane.distance(v) >= -0.0001);
+        }
+    }
+
+    // fully outside
+    assert!(clip_triangle(&vert(2.0, 0.0, 0.5, 1.0), &vert(3.0, 0.0, 0.5, 1.0), &vert(2.5, 0.5, 0.5, 1.0)).is_empty());
+
+    // triangle larger than the frustum is cut down to the 4 sided frustum cross section
+    let big = clip_triangle(&vert(-10.0, -10.0, 0.5, 1.0), &vert(10.0, -10.0, 0.5, 1.0), &vert(0.0, 20.0, 0.5, 1.0));
+    assert_eq!(big.len(), 4);
+}
@@ -0,0 +1,150 @@
+// Clipping of 2D line segments against an axis aligned rectangle such as the canvas viewport
+// or a scissor rect. Works on float coords so nothing is truncated before the clip, and carries
+// depth along so the clipped endpoints get the depth of the point where the cut happened.
+
+// point in canvas space with the depth at that point
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct ClipPoint2d {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32 // clip space w the point was divided by when it was projected, 1 for points drawn straight onto the canvas
+}
+impl ClipPoint2d {
+    // t steps evenly across the canvas, depth is blended through 1 / w so it stays true to the view space
+    // depth under perspective, where even canvas steps are uneven steps in depth
+    pub fn lerp(&self, other:&ClipPoint2d, t:f32) -> Self {
+        let inv_w = (1.0 - t) / self.w + t / other.w;
+        Self {
+            x: self.x + (other.x - self.x) * t,
+            y: self.y + (other.y - self.y) * t,
+            z: ((1.0 - t) * self.z / self.w + t * other.z / other.w) / inv_w,
+            w: 1.0 / inv_w
+        }
+    }
+}
+
+// inclusive bounds, for a pixel grid the max is the index of the last row/column
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct ClipRect {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32
+}
+impl ClipRect {
+    // rect covering every pixel of a width x height canvas
+    pub fn from_canvas_size(width:usize, height:usize) -> Self {
+        Self { min_x: 0.0, min_y: 0.0, max_x: width as f32 - 1.0, max_y: height as f32 - 1.0 }
+    }
+
+    pub fn contains(&self, x:f32, y:f32) -> bool {
+        x >= self.min_x && x <= self.max_x && y >= self.min_y && y <= self.max_y
+    }
+}
+
+// Liang–Barsky, returns the range of the segment parameter t (0.0 at a, 1.0 at b) that lies
+// inside the rect, or None if no part of the segment does
+pub fn clip_segment_params(a:&ClipPoint2d, b:&ClipPoint2d, rect:&ClipRect) -> Option<(f32, f32)> {
+    let delta_x = b.x - a.x;
+    let delta_y = b.y - a.y;
+
+    // (p, q) for each border: left, right, top, bottom
+    // the segment is inside the border where p * t <= q
+    let borders = [
+        (-delta_x, a.x - rect.min_x),
+        ( delta_x, rect.max_x - a.x),
+        (-delta_y, a.y - rect.min_y),
+        ( delta_y, rect.max_y - a.y)
+    ];
+
+    let mut t_enter:f32 = 0.0;
+    let mut t_exit:f32  = 1.0;
+    for (p, q) in borders {
+        if p == 0.0 {
+            // parallel to this border, either completely inside or completely outside of it
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t_enter = t_enter.max(t);
+            } else {
+                t_exit = t_exit.min(t);
+            }
+            if t_enter > t_exit {
+                return None;
+            }
+        }
+    }
+
+    Some((t_enter, t_exit))
+}
+
+// clips the segment a->b to the rect, keeping its direction
+pub fn clip_segment(a:&ClipPoint2d, b:&ClipPoint2d, rect:&ClipRect) -> Option<(ClipPoint2d, ClipPoint2d)> {
+    clip_segment_params(a, b, rect).map(|(t_enter, t_exit)| (a.lerp(b, t_enter), a.lerp(b, t_exit)))
+}
+
+// checks every segment between points on a grid reaching past a small rect on all sides
+#[test]
+fn clip_segment_exhaustive_test() {
+    let rect = ClipRect::from_canvas_size(10, 8);
+    let eps = 0.001;
+    let grown  = ClipRect{min_x:rect.min_x - eps, min_y:rect.min_y - eps, max_x:rect.max_x + eps, max_y:rect.max_y + eps};
+    let shrunk = ClipRect{min_x:rect.min_x + eps, min_y:rect.min_y + eps, max_x:rect.max_x - eps, max_y:rect.max_y - eps};
+    let samples = 64;
+
+    let coords:Vec<f32> = (-6..=30).map(|i| i as f32 * 0.5).collect();
+    for &x0 in coords.iter().step_by(3) {
+        for &y0 in coords.iter().step_by(2) {
+            for &x1 in coords.iter() {
+                for &y1 in coords.iter().step_by(3) {
+                    let a = ClipPoint2d{x:x0, y:y0, z:1.0, w:1.0};
+                    let b = ClipPoint2d{x:x1, y:y1, z:5.0, w:1.0};
+                    let params = clip_segment_params(&a, &b, &rect);
+
+                    // everything kept is inside, everything strictly inside is kept
+                    for sample in 0..=samples {
+                        let t = sample as f32 / samples as f32;
+                        let pt = a.lerp(&b, t);
+                        if params.is_some_and(|(t0, t1)| t >= t0 && t <= t1) {
+                            assert!(grown.contains(pt.x, pt.y), "{:?} -> {:?} kept outside point at t={}", a, b, t);
+                        } else {
+                            assert!(!shrunk.contains(pt.x, pt.y), "{:?} -> {:?} dropped inside point at t={}", a, b, t);
+                        }
+                    }
+
+                    // clipped endpoints sit inside the rect with the depth of where the cut happened
+                    if let Some((t0, t1)) = params {
+                        let (start, end) = clip_segment(&a, &b, &rect).unwrap();
+                        assert!(grown.contains(start.x, start.y) && grown.contains(end.x, end.y));
+                        assert!((start.z - (1.0 + 4.0 * t0)).abs() < eps && (end.z - (1.0 + 4.0 * t1)).abs() < eps);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn clip_segment_one_end_inside_two_borders_test() {
+    // starts inside and leaves through the top right corner region, the case the old clamp missed
+    let rect = ClipRect::from_canvas_size(10, 10);
+    let a = ClipPoint2d{x:8.0, y:1.0, z:0.0, w:1.0};
+    let b = ClipPoint2d{x:12.0, y:-3.0, z:4.0, w:1.0};
+    let (start, end) = clip_segment(&a, &b, &rect).unwrap();
+    assert_eq!(start, a);
+    assert!((end.x - 9.0).abs() < 0.0001 && end.y.abs() < 0.0001 && (end.z - 1.0).abs() < 0.0001);
+}
+
+#[test]
+fn clip_point_perspective_lerp_test() {
+    // a segment running from depth 2 to depth 6 under perspective, where w is the depth, is a quarter of the way
+    // along in view space when it is half way across the canvas, since the near end covers more of the canvas
+    let near = ClipPoint2d{x:0.0, y:0.0, z:2.0, w:2.0};
+    let far = ClipPoint2d{x:10.0, y:0.0, z:6.0, w:6.0};
+    let middle = near.lerp(&far, 0.5);
+    assert!((middle.z - 3.0).abs() < 0.0001 && (middle.w - 3.0).abs() < 0.0001);
+}
@@ -0,0 +1,96 @@
+// rgba color as it is stored in the framebuffer, 0..255 for each channel, alpha 255 is opaque
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Color {
+    pub r:u8,
+    pub g:u8,
+    pub b:u8,
+    pub a:u8
+}
+impl Color {
+    pub const BLACK:Color = Color::rgb(0, 0, 0);
+    pub const WHITE:Color = Color::rgb(255, 255, 255);
+
+    pub const fn rgb(r:u8, g:u8, b:u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+    pub const fn rgba(r:u8, g:u8, b:u8, a:u8) -> Self {
+        Self { r, g, b, a }
+    }
+    // 0..1 for each channel like material and light colors, anything outside is clamped
+    pub fn from_f32(r:f32, g:f32, b:f32, a:f32) -> Self {
+        let [r, g, b, a] = [r, g, b, a].map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+        Self { r, g, b, a }
+    }
+
+    pub fn to_array(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+    pub fn from_array([r, g, b, a]:[u8; 4]) -> Self {
+        Self { r, g, b, a }
+    }
+    // packed 0xRRGGBBAA like a css hex color, handy for passing a whole color in one argument from js
+    pub fn from_rgba_u32(rgba:u32) -> Self {
+        Self::from_array(rgba.to_be_bytes())
+    }
+
+    // self at factor 0 through to other at factor 1, alpha included
+    pub fn lerp(self, other:Color, factor:f32) -> Self {
+        let (from, to) = (self.to_array(), other.to_array());
+        Self::from_array([0, 1, 2, 3].map(|channel| (from[channel] as f32 + (to[channel] as f32 - from[channel] as f32) * factor).round() as u8))
+    }
+
+    // self painted on top of below, letting below show through as much as self is see through
+    pub fn over(self, below:Color) -> Self {
+        let alpha = self.a as f32 / 255.0;
+        let below_alpha = below.a as f32 / 255.0 * (1.0 - alpha);
+        let out_alpha = alpha + below_alpha;
+        if out_alpha == 0.0 {
+            return Self::rgba(0, 0, 0, 0);
+        }
+        let (above, below) = (self.to_array(), below.to_array());
+        let [r, g, b] = [0, 1, 2].map(|channel| ((above[channel] as f32 * alpha + below[channel] as f32 * below_alpha) / out_alpha).round() as u8);
+        Self { r, g, b, a: (out_alpha * 255.0).round() as u8 }
+    }
+}
+
+// what the framebuffer is cleared to before a scene is drawn
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Background {
+    Solid(Color),
+    VerticalGradient { top:Color, bottom:Color } // blended row by row from the top of the canvas to the bottom
+}
+impl Background {
+    // color of row y on a canvas height rows tall
+    pub fn row_color(&self, y:usize, height:usize) -> Color {
+        match *self {
+            Background::Solid(color) => color,
+            Background::VerticalGradient { top, bottom } => top.lerp(bottom, y as f32 / (height.max(2) - 1) as f32)
+        }
+    }
+}
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(Color::WHITE)
+    }
+}
+
+#[test]
+fn color_test() {
+    assert_eq!(Color::from_f32(1.0, 0.5, -1.0, 2.0), Color::rgba(255, 128, 0, 255));
+    assert_eq!(Color::from_rgba_u32(0x11223344), Color::rgba(0x11, 0x22, 0x33, 0x44));
+    assert_eq!(Color::BLACK.lerp(Color::rgba(255, 100, 50, 0), 0.5), Color::rgba(128, 50, 25, 128));
+
+    // opaque covers, clear leaves it be, half see through blends
+    let red = Color::rgb(255, 0, 0);
+    assert_eq!(Color::BLACK.over(red), Color::BLACK);
+    assert_eq!(Color::rgba(0, 0, 255, 0).over(red), red);
+    assert_eq!(Color::rgba(0, 0, 255, 128).over(red), Color::rgb(127, 0, 128));
+    assert_eq!(Color::rgba(0, 0, 255, 128).over(Color::rgba(0, 0, 0, 0)), Color::rgba(0, 0, 255, 128));
+
+    let gradient = Background::VerticalGradient { top: Color::BLACK, bottom: Color::WHITE };
+    assert_eq!(gradient.row_color(0, 5), Color::BLACK);
+    assert_eq!(gradient.row_color(2, 5), Color::rgb(128, 128, 128));
+    assert_eq!(gradient.row_color(4, 5), Color::WHITE);
+    assert_eq!(gradient.row_color(0, 1), Color::BLACK);
+    assert_eq!(Background::default().row_color(3, 5), Color::WHITE);
+}
@@ -0,0 +1,5 @@
+pub mod obj;
+pub mod gltf;
+pub mod stl;
+pub mod png;
+pub mod ppm;
@@ -0,0 +1,674 @@
+use std::fmt;
+
+use base64::Engine;
+use nalgebra::{Matrix3, Quaternion, Rotation3, UnitQuaternion};
+use serde_json::Value;
+
+use crate::scene::camera::*;
+use crate::scene::material::Material;
+use crate::scene::mesh::*;
+use crate::scene::texture::TextureFilter;
+use crate::scene::transform::Transform;
+use crate::scene::*;
+
+const GLB_MAGIC:u32 = 0x4654_6C67; // "glTF"
+const GLB_CHUNK_JSON:u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN:u32 = 0x004E_4942;
+
+// zfar for perspective cameras that leave it out, glTF treats those as infinite
+const INFINITE_ZFAR:f32 = 10000.0;
+
+// no extensions are understood yet, files requiring one are refused and the others get a warning
+const SUPPORTED_EXTENSIONS:[&str; 0] = [];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfError {
+    pub message:String
+}
+impl fmt::Display for GltfError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "gltf: {}", self.message)
+    }
+}
+impl std::error::Error for GltfError {}
+
+fn error<T>(message:String) -> Result<T, GltfError> {
+    Err(GltfError { message })
+}
+
+// what add_gltf_to_scene put in the scene
+pub struct GltfImport {
+    pub root:NodeId, // empty node holding the loaded scene's root nodes
+    pub camera:Option<NodeId>, // node of the first camera found, made the active camera
+    pub warnings:Vec<String> // unsupported extensions, skipped primitives and ignored textures
+}
+
+struct Document<'a> {
+    json:&'a Value,
+    buffers:Vec<Vec<u8>>,
+    warnings:Vec<String>
+}
+
+fn index_field(value:&Value, key:&str) -> Option<usize> {
+    value.get(key).and_then(Value::as_u64).map(|index| index as usize)
+}
+
+fn float_array<const N:usize>(value:&Value, key:&str) -> Result<Option<[f32; N]>, GltfError> {
+    let array = match value.get(key) {
+        Some(array) => array,
+        None => return Ok(None)
+    };
+    let numbers:Vec<f32> = array.as_array().into_iter().flatten().filter_map(Value::as_f64).map(|number| number as f32).collect();
+    match numbers.try_into() {
+        Ok(numbers) => Ok(Some(numbers)),
+        Err(_) => error(format!("\"{}\" should be an array of {} numbers", key, N))
+    }
+}
+
+// splits a .glb into its json and binary chunks, anything else is taken to be .gltf json text
+fn split_glb(bytes:&[u8]) -> Result<(&[u8], Option<&[u8]>), GltfError> {
+    let read_u32 = |offset:usize| bytes.get(offset..offset.checked_add(4)?).map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+    if read_u32(0) != Some(GLB_MAGIC) {
+        return Ok((bytes, None));
+    }
+    if read_u32(4) != Some(2) {
+        return error(format!("glb version {} is not supported, only 2 is", read_u32(4).unwrap_or(0)));
+    }
+    let length = (read_u32(8).unwrap_or(0) as usize).min(bytes.len());
+
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = read_u32(offset).unwrap() as usize;
+        let chunk_type = read_u32(offset + 4).unwrap();
+        let chunk_end = (offset + 8).checked_add(chunk_length);
+        let chunk = match chunk_end.and_then(|chunk_end| bytes.get(offset + 8..chunk_end)) {
+            Some(chunk) => chunk,
+            None => return error(format!("glb chunk at byte {} runs past the end of the file", offset))
+        };
+        match chunk_type {
+            GLB_CHUNK_JSON if json.is_none() => json = Some(chunk),
+            GLB_CHUNK_BIN if bin.is_none() => bin = Some(chunk),
+            _ => {} // unknown chunks are meant to be skipped
+        }
+        offset = chunk_end.unwrap();
+    }
+    match json {
+        Some(json) => Ok((json, bin)),
+        None => error("glb has no json chunk".to_string())
+    }
+}
+
+fn load_buffers(json:&Value, bin:Option<&[u8]>) -> Result<Vec<Vec<u8>>, GltfError> {
+    let mut buffers = Vec::new();
+    for (index, buffer) in json["buffers"].as_array().into_iter().flatten().enumerate() {
+        let data = match buffer.get("uri").and_then(Value::as_str) {
+            None => match (index, bin) {
+                (0, Some(bin)) => bin.to_vec(),
+                _ => return error(format!("buffer {} has no uri and no glb binary chunk to use", index))
+            },
+            Some(uri) if uri.starts_with("data:") => {
+                let encoded = match uri.split_once(";base64,") {
+                    Some((_, encoded)) => encoded,
+                    None => return error(format!("buffer {} data uri is not base64", index))
+                };
+                match base64::engine::general_purpose::STANDARD.decode(encoded) {
+                    Ok(data) => data,
+                    Err(decode_error) => return error(format!("buffer {} data uri: {}", index, decode_error))
+                }
+            }
+            Some(uri) => return error(format!("buffer {} points at external file \"{}\", only embedded buffers are supported", index, uri))
+        };
+        let byte_length = index_field(buffer, "byteLength").unwrap_or(0);
+        if data.len() < byte_length {
+            return error(format!("buffer {} holds {} bytes, {} expected", index, data.len(), byte_length));
+        }
+        buffers.push(data);
+    }
+    Ok(buffers)
+}
+
+impl<'a> Document<'a> {
+    // every element of an accessor as floats, normalized integers are mapped to 0..1 or -1..1
+    fn read_accessor(&self, index:usize) -> Result<Vec<Vec<f32>>, GltfError> {
+        let accessor = match self.json["accessors"].get(index) {
+            Some(accessor) => accessor,
+            None => return error(format!("accessor {} doesn't exist", index))
+        };
+        if accessor.get("sparse").is_some() {
+            return error(format!("accessor {} is sparse, which is not supported", index));
+        }
+        let count = index_field(accessor, "count").unwrap_or(0);
+        let components = match accessor["type"].as_str().unwrap_or("") {
+            "SCALAR" => 1,
+            "VEC2" => 2,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            other => return error(format!("accessor {} has type \"{}\", only SCALAR and VEC2 to VEC4 are supported", index, other))
+        };
+        let component_type = index_field(accessor, "componentType").unwrap_or(0);
+        let component_size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            other => return error(format!("accessor {} has unknown component type {}", index, other))
+        };
+        let normalized = accessor["normalized"].as_bool().unwrap_or(false);
+
+        let view = match index_field(accessor, "bufferView") {
+            Some(view_index) => match self.json["bufferViews"].get(view_index) {
+                Some(view) => view,
+                None => return error(format!("accessor {} uses buffer view {} which doesn't exist", index, view_index))
+            },
+            // no data means all zeros, but the count still can't be more than the file's buffers could hold
+            None => {
+                let buffer_bytes:usize = self.buffers.iter().map(Vec::len).sum();
+                if count.checked_mul(components * component_size).is_none_or(|size| size > buffer_bytes) {
+                    return error(format!("accessor {} has no buffer view and claims {} elements, more than the buffers hold", index, count));
+                }
+                return Ok(vec![vec![0.0; components]; count]);
+            }
+        };
+        let buffer = match index_field(view, "buffer").and_then(|buffer_index| self.buffers.get(buffer_index)) {
+            Some(buffer) => buffer,
+            None => return error(format!("buffer view of accessor {} points at a missing buffer", index))
+        };
+        let element_size = components * component_size;
+        let stride = match index_field(view, "byteStride") {
+            None => element_size,
+            // glTF keeps strides 4 byte aligned, and one shorter than an element would read elements over each other
+            Some(stride) if stride >= element_size && (4..=252).contains(&stride) && stride % 4 == 0 => stride,
+            Some(stride) => return error(format!("accessor {} has byte stride {}, it should be a multiple of 4 from 4 to 252 and at least {}", index, stride, element_size))
+        };
+        let start = index_field(view, "byteOffset").unwrap_or(0).checked_add(index_field(accessor, "byteOffset").unwrap_or(0));
+        // every element moves the end on by at least a byte, so count can't claim more elements than the buffer has bytes
+        let end = start.and_then(|start| stride.checked_mul(count.saturating_sub(1))?.checked_add(element_size)?.checked_add(start));
+        let start = match (start, end) {
+            (Some(start), Some(end)) if count == 0 || end <= buffer.len() => start,
+            _ => return error(format!("accessor {} reads past the end of its buffer", index))
+        };
+
+        let read_component = |offset:usize| -> f32 {
+            let bytes = &buffer[offset..offset + component_size];
+            match (component_type, normalized) {
+                (5120, false) => bytes[0] as i8 as f32,
+                (5120, true) => (bytes[0] as i8 as f32 / 127.0).max(-1.0),
+                (5121, false) => bytes[0] as f32,
+                (5121, true) => bytes[0] as f32 / 255.0,
+                (5122, false) => i16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+                (5122, true) => (i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32767.0).max(-1.0),
+                (5123, false) => u16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+                (5123, true) => u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 65535.0,
+                (5125, _) => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32,
+                _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+            }
+        };
+        Ok((0..count)
+            .map(|element| (0..components).map(|component| read_component(start + element * stride + component * component_size)).collect())
+            .collect())
+    }
+
+    // an attribute's elements after checking its accessor type is the one the attribute needs, the type sets
+    // how many components every element has so they can be indexed without checking each one
+    fn read_attribute(&self, index:usize, attribute:&str, expected_type:&str) -> Result<Vec<Vec<f32>>, GltfError> {
+        if let Some(accessor) = self.json["accessors"].get(index) {
+            let found = accessor["type"].as_str().unwrap_or("");
+            if found != expected_type {
+                return error(format!("{} accessor {} has type \"{}\", {} expected", attribute, index, found, expected_type));
+            }
+        }
+        self.read_accessor(index)
+    }
+
+    // indices are read as integers so large u32 values don't lose precision going through f32
+    fn read_indices(&self, index:usize, vert_count:usize) -> Result<Vec<usize>, GltfError> {
+        let accessor = &self.json["accessors"][index];
+        if accessor["type"].as_str() != Some("SCALAR") || !matches!(index_field(accessor, "componentType"), Some(5121 | 5123 | 5125)) {
+            return error(format!("index accessor {} should be unsigned integer scalars", index));
+        }
+        let indices:Vec<usize> = match index_field(accessor, "componentType") {
+            Some(5125) => {
+                let view = &self.json["bufferViews"][index_field(accessor, "bufferView").unwrap_or(usize::MAX)];
+                let buffer = index_field(view, "buffer").and_then(|buffer_index| self.buffers.get(buffer_index));
+                let start = index_field(view, "byteOffset").unwrap_or(0).checked_add(index_field(accessor, "byteOffset").unwrap_or(0));
+                let count = index_field(accessor, "count").unwrap_or(0);
+                let range = start.and_then(|start| Some(start..count.checked_mul(4)?.checked_add(start)?));
+                match buffer.zip(range).and_then(|(buffer, range)| buffer.get(range)) {
+                    Some(bytes) => bytes.chunks_exact(4).map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]) as usize).collect(),
+                    None => return error(format!("index accessor {} reads past the end of its buffer", index))
+                }
+            }
+            _ => self.read_accessor(index)?.iter().map(|element| element[0] as usize).collect()
+        };
+        if let Some(bad_index) = indices.iter().find(|&&vert| vert >= vert_count) {
+            return error(format!("index accessor {} refers to vert {} of {}", index, bad_index, vert_count));
+        }
+        Ok(indices)
+    }
+
+    // every triangle primitive of the mesh merged into one Mesh, None if none could be used
+    fn load_mesh(&mut self, index:usize) -> Result<Option<Mesh>, GltfError> {
+        let primitives = match self.json["meshes"].get(index) {
+            Some(mesh) => mesh["primitives"].as_array().cloned().unwrap_or_default(),
+            None => return error(format!("mesh {} doesn't exist", index))
+        };
+        let mut merged = Mesh::new(Vec::new(), Vec::new());
+        let mut any_normals = false;
+        let mut any_uvs = false;
+        let mut material_index = None;
+        for (primitive_index, primitive) in primitives.iter().enumerate() {
+            let mode = index_field(primitive, "mode").unwrap_or(4);
+            if !(4..=6).contains(&mode) {
+                self.warnings.push(format!("mesh {} primitive {} skipped, mode {} is points or lines", index, primitive_index, mode));
+                continue;
+            }
+            let attributes = &primitive["attributes"];
+            let position_accessor = match index_field(attributes, "POSITION") {
+                Some(accessor) => accessor,
+                None => {
+                    self.warnings.push(format!("mesh {} primitive {} skipped, it has no POSITION attribute", index, primitive_index));
+                    continue;
+                }
+            };
+
+            let positions = self.read_attribute(position_accessor, "POSITION", "VEC3")?;
+            let offset = merged.verts.len();
+            merged.verts.extend(positions.iter().map(|pos| Vert3{x:pos[0], y:pos[1], z:pos[2]}));
+            match index_field(attributes, "NORMAL") {
+                Some(accessor) => {
+                    merged.normals.resize(offset, Vert3{x:0.0, y:0.0, z:0.0});
+                    merged.normals.extend(self.read_attribute(accessor, "NORMAL", "VEC3")?.iter().map(|normal| Vert3{x:normal[0], y:normal[1], z:normal[2]}));
+                    any_normals = true;
+                }
+                None => merged.normals.resize(offset + positions.len(), Vert3{x:0.0, y:0.0, z:0.0})
+            }
+            match index_field(attributes, "TEXCOORD_0") {
+                Some(accessor) => {
+                    merged.uvs.resize(offset, TexCoord{u:0.0, v:0.0});
+                    // glTF puts v = 0 at the top of the image
+                    merged.uvs.extend(self.read_attribute(accessor, "TEXCOORD_0", "VEC2")?.iter().map(|uv| TexCoord{u:uv[0], v:1.0 - uv[1]}));
+                    any_uvs = true;
+                }
+                None => merged.uvs.resize(offset + positions.len(), TexCoord{u:0.0, v:0.0})
+            }
+            if merged.normals.len() != merged.verts.len() || merged.uvs.len() != merged.verts.len() {
+                return error(format!("mesh {} primitive {} attributes have different counts", index, primitive_index));
+            }
+
+            let indices = match index_field(primitive, "indices") {
+                Some(accessor) => self.read_indices(accessor, positions.len())?,
+                None => (0..positions.len()).collect()
+            };
+            let tris:Vec<[usize; 3]> = match mode {
+                4 => indices.chunks_exact(3).map(|tri| [tri[0], tri[1], tri[2]]).collect(),
+                // strips flip every other triangle to keep the winding the same
+                5 => indices.windows(3).enumerate().map(|(n, tri)| if n % 2 == 0 { [tri[0], tri[1], tri[2]] } else { [tri[1], tri[0], tri[2]] }).collect(),
+                _ => (1..indices.len().saturating_sub(1)).map(|n| [indices[0], indices[n], indices[n + 1]]).collect()
+            };
+            for tri in &tris {
+                merged.tris.extend(tri.iter().map(|vert| vert + offset));
+            }
+
+            // the merged mesh can only have one material, the first primitive to name one picks it
+            match (material_index, index_field(primitive, "material")) {
+                (None, Some(primitive_material)) => {
+                    merged.material = self.load_material(primitive_material)?;
+                    material_index = Some(primitive_material);
+                }
+                (Some(used), Some(primitive_material)) if used != primitive_material => {
+                    self.warnings.push(format!("mesh {} primitive {} drawn with material {} instead of {}", index, primitive_index, used, primitive_material));
+                }
+                _ => {}
+            }
+        }
+        if !any_normals {
+            merged.normals.clear();
+        }
+        if !any_uvs {
+            merged.uvs.clear();
+        }
+        // glTF is right handed with the camera looking down -z, mirroring z makes it our left handed space
+        merged.flip_handedness();
+        Ok(if merged.tris.is_empty() { None } else { Some(merged) })
+    }
+
+    // metallic roughness turned into the nearest blinn-phong material, textures aren't read so each one gets a warning
+    fn load_material(&mut self, index:usize) -> Result<Material, GltfError> {
+        let material = match self.json["materials"].get(index) {
+            Some(material) => material,
+            None => return error(format!("material {} doesn't exist", index))
+        };
+        let pbr = &material["pbrMetallicRoughness"];
+        for (owner, key) in [(pbr, "baseColorTexture"), (pbr, "metallicRoughnessTexture"), (material, "normalTexture"), (material, "occlusionTexture"), (material, "emissiveTexture")] {
+            if owner.get(key).is_some() {
+                self.warnings.push(format!("material {} {} ignored, textures aren't loaded", index, key));
+            }
+        }
+        let base = float_array::<4>(pbr, "baseColorFactor")?.unwrap_or([1.0; 4]);
+        let emissive = float_array::<3>(material, "emissiveFactor")?.unwrap_or([0.0; 3]);
+        let number = |key:&str| pbr.get(key).and_then(Value::as_f64).map(|number| number as f32);
+        let (metallic, roughness) = (number("metallicFactor").unwrap_or(1.0).clamp(0.0, 1.0), number("roughnessFactor").unwrap_or(1.0).clamp(0.0, 1.0));
+        // metals reflect their base color and have no diffuse part, everything else reflects about 4% in white
+        let diffuse = |channel:f32| channel * (1.0 - metallic);
+        let specular = |channel:f32| 0.04 + (channel - 0.04) * metallic;
+        // usual match between a roughness and a blinn-phong exponent, 2 / roughness^4 - 2
+        let alpha = (roughness * roughness).max(0.01);
+        Ok(Material {
+            base_color: (diffuse(base[0]), diffuse(base[1]), diffuse(base[2])),
+            texture: None,
+            texture_filter: TextureFilter::Bilinear,
+            specular_color: (specular(base[0]), specular(base[1]), specular(base[2])),
+            shininess: (2.0 / (alpha * alpha) - 2.0).max(1.0),
+            emissive: (emissive[0], emissive[1], emissive[2])
+        })
+    }
+
+    fn load_camera(&self, index:usize) -> Result<Camera, GltfError> {
+        let camera = match self.json["cameras"].get(index) {
+            Some(camera) => camera,
+            None => return error(format!("camera {} doesn't exist", index))
+        };
+        let number = |projection:&Value, key:&str| projection.get(key).and_then(Value::as_f64).map(|number| number as f32);
+        match camera["type"].as_str() {
+            Some("perspective") => {
+                let projection = &camera["perspective"];
+                let yfov = number(projection, "yfov").unwrap_or(std::f32::consts::FRAC_PI_2);
+                let znear = number(projection, "znear").unwrap_or(0.1);
+                Ok(Camera::new(yfov.to_degrees(), znear, number(projection, "zfar").unwrap_or(INFINITE_ZFAR)))
+            }
+            Some("orthographic") => {
+                let projection = &camera["orthographic"];
+                let (xmag, ymag) = (number(projection, "xmag").unwrap_or(1.0), number(projection, "ymag").unwrap_or(1.0));
+                Ok(Camera::with_projection(
+                    Projection::OrthographicBounds { left: -xmag, right: xmag, bottom: -ymag, top: ymag },
+                    number(projection, "znear").unwrap_or(0.0),
+                    number(projection, "zfar").unwrap_or(INFINITE_ZFAR)
+                ))
+            }
+            other => error(format!("camera {} has unknown type {:?}", index, other))
+        }
+    }
+}
+
+// glTF node transforms are either TRS or a column major matrix, mirrored into our left handed space
+fn node_transform(node:&Value) -> Result<Transform, GltfError> {
+    let mut transform = Transform::identity();
+    if let Some(m) = float_array::<16>(node, "matrix")? {
+        // split the matrix into scale, rotation and translation, shear is lost
+        let columns = Matrix3::new(m[0], m[4], m[8], m[1], m[5], m[9], m[2], m[6], m[10]);
+        let mut scale = [columns.column(0).norm(), columns.column(1).norm(), columns.column(2).norm()];
+        if columns.determinant() < 0.0 {
+            scale[0] = -scale[0]; // a mirroring matrix becomes a negative scale
+        }
+        let mut rotation = columns;
+        for (axis, axis_scale) in scale.iter().enumerate() {
+            if *axis_scale != 0.0 {
+                rotation.set_column(axis, &(columns.column(axis) / *axis_scale));
+            }
+        }
+        let rotation = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix(&rotation));
+        transform.translation = Vert3{x:m[12], y:m[13], z:-m[14]};
+        transform.rotation = mirror_rotation(rotation);
+        transform.scale = Vert3{x:scale[0], y:scale[1], z:scale[2]};
+        return Ok(transform);
+    }
+    if let Some([x, y, z]) = float_array::<3>(node, "translation")? {
+        transform.translation = Vert3{x, y, z:-z};
+    }
+    if let Some([x, y, z, w]) = float_array::<4>(node, "rotation")? {
+        transform.rotation = mirror_rotation(UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z)));
+    }
+    if let Some([x, y, z]) = float_array::<3>(node, "scale")? {
+        transform.scale = Vert3{x, y, z};
+    }
+    Ok(transform)
+}
+
+// the same rotation seen in the z mirrored space turns the other way around x and y
+fn mirror_rotation(rotation:UnitQuaternion<f32>) -> UnitQuaternion<f32> {
+    let q = rotation.quaternion();
+    UnitQuaternion::from_quaternion(Quaternion::new(q.w, -q.i, -q.j, q.k))
+}
+
+// adds the glTF nodes and their subtrees below root, returns the node of the first camera
+fn add_nodes(scene:&mut Scene, document:&mut Document, roots:&[usize], root:NodeId) -> Result<Option<NodeId>, GltfError> {
+    let json = document.json;
+    let node_count = json["nodes"].as_array().map_or(0, Vec::len);
+    let mut camera = None;
+    let mut visited = vec![false; node_count];
+    let mut stack:Vec<(usize, NodeId)> = roots.iter().rev().map(|&index| (index, root)).collect();
+    while let Some((index, parent_id)) = stack.pop() {
+        let gltf_node = match json["nodes"].get(index) {
+            Some(gltf_node) => gltf_node,
+            None => return error(format!("node {} doesn't exist", index))
+        };
+        if std::mem::replace(&mut visited[index], true) {
+            return error(format!("node {} appears more than once in the hierarchy", index));
+        }
+
+        let mut node = SceneNode::empty();
+        node.name = gltf_node["name"].as_str().unwrap_or("").to_string();
+        node.transform = node_transform(gltf_node)?;
+        if let Some(mesh_index) = index_field(gltf_node, "mesh") {
+            node.mesh = document.load_mesh(mesh_index)?;
+        }
+        if let Some(camera_index) = index_field(gltf_node, "camera") {
+            node.camera = Some(document.load_camera(camera_index)?);
+        }
+        let has_camera = node.camera.is_some();
+        let id = scene.add_node(Some(parent_id), node).expect("parent was added before its children");
+        if has_camera && camera.is_none() {
+            camera = Some(id);
+        }
+
+        let children:Vec<usize> = gltf_node["children"].as_array().into_iter().flatten().filter_map(Value::as_u64).map(|child| child as usize).collect();
+        stack.extend(children.iter().rev().map(|&child| (child, id)));
+    }
+    Ok(camera)
+}
+
+// loads a .gltf (json text with embedded buffers) or .glb file under parent, or as a new root when parent is None
+// the file's default scene is used, or its first one, or every root node if it lists no scenes
+pub fn add_gltf_to_scene(scene:&mut Scene, parent:Option<NodeId>, bytes:&[u8]) -> Result<GltfImport, GltfError> {
+    let (json_bytes, bin) = split_glb(bytes)?;
+    let json:Value = match serde_json::from_slice(json_bytes) {
+        Ok(json) => json,
+        Err(parse_error) => return error(format!("invalid json: {}", parse_error))
+    };
+    let version = json["asset"]["version"].as_str().unwrap_or("");
+    if !version.starts_with("2.") {
+        return error(format!("asset version \"{}\" is not supported, only 2.x is", version));
+    }
+
+    let mut warnings = Vec::new();
+    let is_supported = |name:&&str| SUPPORTED_EXTENSIONS.contains(name);
+    let required:Vec<&str> = json["extensionsRequired"].as_array().into_iter().flatten().filter_map(Value::as_str).collect();
+    let unsupported_required:Vec<&str> = required.iter().copied().filter(|name| !is_supported(name)).collect();
+    if !unsupported_required.is_empty() {
+        return error(format!("requires unsupported extensions: {}", unsupported_required.join(", ")));
+    }
+    for name in json["extensionsUsed"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+        if !is_supported(&name) {
+            warnings.push(format!("extension {} is not supported and was ignored", name));
+        }
+    }
+
+    let buffers = load_buffers(&json, bin)?;
+    let mut document = Document { json: &json, buffers, warnings };
+
+    let node_count = json["nodes"].as_array().map_or(0, Vec::len);
+    let roots:Vec<usize> = match index_field(&json, "scene").or(if json["scenes"].as_array().is_some_and(|scenes| !scenes.is_empty()) { Some(0) } else { None }) {
+        Some(scene_index) => match json["scenes"].get(scene_index) {
+            Some(gltf_scene) => gltf_scene["nodes"].as_array().into_iter().flatten().filter_map(Value::as_u64).map(|index| index as usize).collect(),
+            None => return error(format!("scene {} doesn't exist", scene_index))
+        },
+        None => {
+            let children:Vec<u64> = json["nodes"].as_array().into_iter().flatten()
+                .flat_map(|node| node["children"].as_array().cloned().unwrap_or_default())
+                .filter_map(|child| child.as_u64())
+                .collect();
+            (0..node_count).filter(|index| !children.contains(&(*index as u64))).collect()
+        }
+    };
+
+    let root = match scene.add_node(parent, SceneNode::empty()) {
+        Some(root) => root,
+        None => return error("parent node doesn't exist".to_string())
+    };
+    // nothing is left behind in the scene when the file turns out to be broken part way through
+    let camera = match add_nodes(scene, &mut document, &roots, root) {
+        Ok(camera) => camera,
+        Err(load_error) => {
+            scene.remove_node(root);
+            return Err(load_error);
+        }
+    };
+    if camera.is_some() {
+        scene.set_active_camera(camera);
+    }
+    Ok(GltfImport { root, camera, warnings: document.warnings })
+}
+
+#[cfg(test)]
+fn test_gltf_json(buffer_uri:Option<&str>, buffer_length:usize) -> String {
+    let uri = buffer_uri.map_or(String::new(), |uri| format!("\"uri\": \"{}\", ", uri));
+    format!(r#"{{
+        "asset": {{ "version": "2.0" }},
+        "extensionsUsed": ["KHR_materials_unlit"],
+        "scene": 0,
+        "scenes": [{{ "nodes": [0] }}],
+        "nodes": [
+            {{ "name": "arm", "translation": [0, 0, -10], "children": [1, 2] }},
+            {{ "name": "hand", "mesh": 0, "scale": [2, 2, 2], "rotation": [0, 0.7071068, 0, 0.7071068] }},
+            {{ "name": "eye", "camera": 0, "matrix": [1,0,0,0, 0,1,0,0, 0,0,1,0, 1,2,3,1] }}
+        ],
+        "cameras": [{{ "type": "perspective", "perspective": {{ "yfov": 1.0, "znear": 0.5, "zfar": 50 }} }}],
+        "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "material": 0 }}, {{ "attributes": {{ "POSITION": 0 }}, "mode": 1 }}] }}],
+        "materials": [{{ "pbrMetallicRoughness": {{ "baseColorFactor": [1, 0.5, 0.25, 1], "baseColorTexture": {{ "index": 0 }}, "metallicFactor": 0, "roughnessFactor": 0.5 }}, "emissiveFactor": [0.1, 0, 0] }}],
+        "accessors": [
+            {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }},
+            {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+        ],
+        "bufferViews": [
+            {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+            {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }}
+        ],
+        "buffers": [{{ {}"byteLength": {} }}]
+    }}"#, uri, buffer_length)
+}
+
+#[cfg(test)]
+fn test_gltf_buffer() -> Vec<u8> {
+    let mut buffer = Vec::new();
+    for value in [0.0f32, 0.0, 0.0,  1.0, 0.0, 0.0,  0.0, 1.0, 0.0] {
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+    for index in [0u16, 1, 2] {
+        buffer.extend_from_slice(&index.to_le_bytes());
+    }
+    buffer
+}
+
+#[cfg(test)]
+fn check_test_scene(scene:&Scene, import:&GltfImport) {
+    let close = |a:Vert3, b:Vert3| (a.x - b.x).abs() < 0.0001 && (a.y - b.y).abs() < 0.0001 && (a.z - b.z).abs() < 0.0001;
+
+    let arm = scene.node(import.root).unwrap().children()[0];
+    let [hand, eye] = [scene.node(arm).unwrap().children()[0], scene.node(arm).unwrap().children()[1]];
+    assert_eq!(scene.node(arm).unwrap().name, "arm");
+    assert_eq!(scene.node(hand).unwrap().name, "hand");
+
+    // z is mirrored, so the arm ends up in front of the origin and triangles keep facing the same way
+    let mesh = scene.node(hand).unwrap().mesh.as_ref().unwrap();
+    assert_eq!(mesh.tris, vec![0, 2, 1]);
+    assert_eq!(mesh.verts[1], Vert3{x:1.0, y:0.0, z:-0.0});
+    assert_eq!(mesh.material, Material { base_color: (1.0, 0.5, 0.25), texture: None, texture_filter: TextureFilter::Bilinear, specular_color: (0.04, 0.04, 0.04), shininess: 30.0, emissive: (0.1, 0.0, 0.0) });
+
+    // a quarter turn around y takes +x to -z in glTF, which is +z here, then doubled by the scale
+    let world = scene.world_matrix(hand).unwrap();
+    assert!(close(mesh.verts[1].transformed(&world), Vert3{x:0.0, y:0.0, z:12.0}));
+
+    // the first camera is active and placed by its node's matrix
+    assert_eq!(import.camera, Some(eye));
+    assert_eq!(scene.active_camera_node(), Some(eye));
+    assert!(close(Vert3{x:0.0, y:0.0, z:0.0}.transformed(&scene.world_matrix(eye).unwrap()), Vert3{x:1.0, y:2.0, z:7.0}));
+    let camera = scene.node(eye).unwrap().camera.as_ref().unwrap();
+    assert_eq!((camera.znear, camera.zfar), (0.5, 50.0));
+
+    assert_eq!(import.warnings.len(), 3);
+    assert!(import.warnings[0].contains("KHR_materials_unlit"));
+    assert_eq!(import.warnings[1], "material 0 baseColorTexture ignored, textures aren't loaded");
+    assert!(import.warnings[2].contains("mode 1"));
+}
+
+#[test]
+fn load_gltf_test() {
+    let buffer = test_gltf_buffer();
+    let uri = format!("data:application/octet-stream;base64,{}", base64::engine::general_purpose::STANDARD.encode(&buffer));
+    let mut scene = Scene::new(Camera::new_default());
+    let import = add_gltf_to_scene(&mut scene, None, test_gltf_json(Some(&uri), buffer.len()).as_bytes()).unwrap();
+    check_test_scene(&scene, &import);
+}
+
+#[test]
+fn load_glb_test() {
+    let mut buffer = test_gltf_buffer();
+    buffer.resize(buffer.len().next_multiple_of(4), 0);
+    let mut json = test_gltf_json(None, buffer.len()).into_bytes();
+    json.resize(json.len().next_multiple_of(4), b' ');
+
+    let mut glb = Vec::new();
+    for word in [GLB_MAGIC, 2, (12 + 8 + json.len() + 8 + buffer.len()) as u32, json.len() as u32, GLB_CHUNK_JSON] {
+        glb.extend_from_slice(&word.to_le_bytes());
+    }
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(buffer.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&GLB_CHUNK_BIN.to_le_bytes());
+    glb.extend_from_slice(&buffer);
+
+    let mut scene = Scene::new(Camera::new_default());
+    let import = add_gltf_to_scene(&mut scene, None, &glb).unwrap();
+    check_test_scene(&scene, &import);
+}
+
+#[test]
+fn gltf_error_test() {
+    let mut scene = Scene::new(Camera::new_default());
+    let mut load = |text:&str| add_gltf_to_scene(&mut scene, None, text.as_bytes()).err().unwrap().message;
+    assert!(load(r#"{ "asset": { "version": "1.0" } }"#).contains("only 2.x"));
+    assert_eq!(load(r#"{ "asset": { "version": "2.0" }, "extensionsRequired": ["KHR_draco_mesh_compression"] }"#),
+        "requires unsupported extensions: KHR_draco_mesh_compression");
+    assert!(load(r#"{ "asset": { "version": "2.0" }, "buffers": [{ "uri": "mesh.bin", "byteLength": 4 }] }"#).contains("only embedded buffers"));
+    assert!(load("not json").starts_with("invalid json"));
+
+    // sizes claimed by the file that would overflow or allocate far more than the file holds
+    let mesh_json = |accessor:&str, indices:&str| format!(r#"{{ "asset": {{ "version": "2.0" }}, "scenes": [{{ "nodes": [0] }}], "nodes": [{{ "mesh": 0 }}],
+        "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}{} }}] }}], "accessors": [{}, {}],
+        "bufferViews": [{{ "buffer": 0 }}], "buffers": [{{ "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAA", "byteLength": 12 }}] }}"#,
+        if indices.is_empty() { "" } else { r#", "indices": 1"# }, accessor, if indices.is_empty() { "{}" } else { indices });
+    let flat = r#"{ "bufferView": 0, "componentType": 5126, "count": 1, "type": "VEC2" }"#;
+    assert_eq!(load(&mesh_json(flat, "")), "POSITION accessor 0 has type \"VEC2\", VEC3 expected");
+    let unviewed = r#"{ "componentType": 5126, "count": 1000000000000, "type": "VEC3" }"#;
+    assert!(load(&mesh_json(unviewed, "")).contains("no buffer view and claims 1000000000000 elements"));
+    let far = format!(r#"{{ "bufferView": 0, "byteOffset": {}, "componentType": 5126, "count": 2, "type": "VEC3" }}"#, usize::MAX - 4);
+    assert!(load(&mesh_json(&far, "")).contains("reads past the end"));
+    let strided = |stride:usize| format!(r#"{{ "asset": {{ "version": "2.0" }}, "scenes": [{{ "nodes": [0] }}], "nodes": [{{ "mesh": 0 }}],
+        "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }} }}] }}], "accessors": [{{ "bufferView": 0, "componentType": 5126, "count": 100000000, "type": "VEC3" }}],
+        "bufferViews": [{{ "buffer": 0, "byteStride": {} }}], "buffers": [{{ "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAA", "byteLength": 12 }}] }}"#, stride);
+    for stride in [0, 8, 14, 256] {
+        assert!(load(&strided(stride)).contains(&format!("byte stride {}", stride)));
+    }
+    assert!(load(&strided(12)).contains("reads past the end"));
+    let position = r#"{ "bufferView": 0, "componentType": 5126, "count": 1, "type": "VEC3" }"#;
+    let indices = format!(r#"{{ "bufferView": 0, "byteOffset": 4, "componentType": 5125, "count": {}, "type": "SCALAR" }}"#, usize::MAX / 4);
+    assert!(load(&mesh_json(position, &indices)).contains("reads past the end"));
+
+    let mut glb = Vec::new();
+    for word in [GLB_MAGIC, 2, 40, u32::MAX, GLB_CHUNK_JSON] {
+        glb.extend_from_slice(&word.to_le_bytes());
+    }
+    glb.resize(40, b' ');
+    assert!(add_gltf_to_scene(&mut scene, None, &glb).err().unwrap().message.contains("runs past the end of the file"));
+    assert!(scene.nodes.is_empty());
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::scene::mesh::*;
+
+// what went wrong and on which line (counting from 1) of the obj text
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjError {
+    pub line:usize,
+    pub message:String
+}
+impl fmt::Display for ObjError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "obj line {}: {}", self.line, self.message)
+    }
+}
+impl std::error::Error for ObjError {}
+
+// faces between one "o" or "g" statement and the next, with their own copy of the verts they use
+pub struct ObjGroup {
+    pub name:String,
+    pub mesh:Mesh
+}
+
+// builds one group's mesh, a vert is made for every distinct position/uv/normal index combination
+struct GroupBuilder {
+    name:String,
+    mesh:Mesh,
+    has_uvs:bool,
+    has_normals:bool,
+    vert_lookup:HashMap<(usize, Option<usize>, Option<usize>), usize>
+}
+impl GroupBuilder {
+    fn new(name:&str) -> Self {
+        Self { name: name.to_string(), mesh: Mesh::new(Vec::new(), Vec::new()), has_uvs: false, has_normals: false, vert_lookup: HashMap::new() }
+    }
+
+    fn vert_index(&mut self, key:(usize, Option<usize>, Option<usize>), positions:&[Vert3], uvs:&[TexCoord], normals:&[Vert3]) -> usize {
+        if let Some(&index) = self.vert_lookup.get(&key) {
+            return index;
+        }
+        let index = self.mesh.verts.len();
+        self.mesh.verts.push(positions[key.0]);
+        self.mesh.uvs.push(key.1.map_or(TexCoord{u:0.0, v:0.0}, |uv| uvs[uv]));
+        self.mesh.normals.push(key.2.map_or(Vert3{x:0.0, y:0.0, z:0.0}, |normal| normals[normal]));
+        self.has_uvs |= key.1.is_some();
+        self.has_normals |= key.2.is_some();
+        self.vert_lookup.insert(key, index);
+        index
+    }
+
+    // None if no faces were added, the attributes no face referenced are dropped
+    fn finish(mut self) -> Option<ObjGroup> {
+        if self.mesh.tris.is_empty() {
+            return None;
+        }
+        if !self.has_uvs {
+            self.mesh.uvs.clear();
+        }
+        if !self.has_normals {
+            self.mesh.normals.clear();
+        }
+        // obj files are right handed like opengl, mirroring z makes them our left handed space
+        self.mesh.flip_handedness();
+        Some(ObjGroup { name: self.name, mesh: self.mesh })
+    }
+}
+
+// missing optional values come back as 0
+fn parse_floats(fields:&[&str], min:usize, max:usize, line:usize, what:&str) -> Result<Vec<f32>, ObjError> {
+    if fields.len() < min || fields.len() > max {
+        let expected = if min == max { min.to_string() } else { format!("{} to {}", min, max) };
+        return Err(ObjError { line, message: format!("{} needs {} numbers, found {}", what, expected, fields.len()) });
+    }
+    let mut values = vec![0.0; max];
+    for (value, field) in values.iter_mut().zip(fields) {
+        *value = field.parse().map_err(|_| ObjError { line, message: format!("{} has \"{}\" where a number should be", what, field) })?;
+    }
+    Ok(values)
+}
+
+// 1 is the first element, -1 the last one defined so far, 0 is never valid
+fn resolve_index(field:&str, count:usize, line:usize, what:&str) -> Result<usize, ObjError> {
+    let index:i64 = field.parse().map_err(|_| ObjError { line, message: format!("{} index \"{}\" is not a whole number", what, field) })?;
+    let resolved = if index > 0 { index - 1 } else { count as i64 + index };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(ObjError { line, message: format!("{} index {} is out of range, {} defined so far", what, index, count) });
+    }
+    Ok(resolved as usize)
+}
+
+// splits the obj text into one mesh per group, faces before the first "o" or "g" go in a group named "default"
+// polygons are split into a fan of triangles, so they are expected to be convex
+pub fn parse_obj_groups(text:&str) -> Result<Vec<ObjGroup>, ObjError> {
+    let mut positions:Vec<Vert3> = Vec::new();
+    let mut uvs:Vec<TexCoord> = Vec::new();
+    let mut normals:Vec<Vert3> = Vec::new();
+
+    let mut groups:Vec<ObjGroup> = Vec::new();
+    let mut current = GroupBuilder::new("default");
+
+    for (line_index, raw_line) in text.lines().enumerate() {
+        let line = line_index + 1;
+        let content = raw_line.split('#').next().unwrap_or("");
+        let mut fields = content.split_whitespace();
+        let keyword = match fields.next() {
+            Some(keyword) => keyword,
+            None => continue // blank or comment
+        };
+        let fields:Vec<&str> = fields.collect();
+
+        match keyword {
+            "v" => {
+                // the optional w weight is only used by rational curves, some exporters put r g b vertex
+                // colors after the position instead, neither is drawn so anything past z is ignored
+                let values = parse_floats(&fields, 3, 7, line, "vertex")?;
+                positions.push(Vert3{x:values[0], y:values[1], z:values[2]});
+            }
+            "vt" => {
+                let values = parse_floats(&fields, 1, 3, line, "texture coordinate")?;
+                uvs.push(TexCoord{u:values[0], v:values[1]});
+            }
+            "vn" => {
+                let values = parse_floats(&fields, 3, 3, line, "normal")?;
+                normals.push(Vert3{x:values[0], y:values[1], z:values[2]});
+            }
+            "f" => {
+                if fields.len() < 3 {
+                    return Err(ObjError { line, message: format!("face needs at least 3 corners, found {}", fields.len()) });
+                }
+                let mut corners = Vec::with_capacity(fields.len());
+                for field in &fields {
+                    // v, v/vt, v//vn or v/vt/vn
+                    let mut parts = field.split('/');
+                    let position = resolve_index(parts.next().unwrap_or(""), positions.len(), line, "vertex")?;
+                    let uv = match parts.next() {
+                        Some("") | None => None,
+                        Some(part) => Some(resolve_index(part, uvs.len(), line, "texture coordinate")?)
+                    };
+                    let normal = match parts.next() {
+                        Some("") | None => None,
+                        Some(part) => Some(resolve_index(part, normals.len(), line, "normal")?)
+                    };
+                    if parts.next().is_some() {
+                        return Err(ObjError { line, message: format!("face corner \"{}\" has more than 3 indices", field) });
+                    }
+                    corners.push(current.vert_index((position, uv, normal), &positions, &uvs, &normals));
+                }
+                for index in 1..corners.len() - 1 {
+                    current.mesh.tris.extend_from_slice(&[corners[0], corners[index], corners[index + 1]]);
+                }
+            }
+            "o" | "g" => {
+                let name = if fields.is_empty() { "default".to_string() } else { fields.join(" ") };
+                let finished = std::mem::replace(&mut current, GroupBuilder::new(&name));
+                groups.extend(finished.finish());
+            }
+            // materials, smoothing groups, lines, points and the rest don't change the geometry we draw
+            _ => {}
+        }
+    }
+    groups.extend(current.finish());
+    Ok(groups)
+}
+
+impl Mesh {
+    // every group of the obj text merged into one mesh
+    pub fn from_obj(text:&str) -> Result<Self, ObjError> {
+        let mut merged = Mesh::new(Vec::new(), Vec::new());
+        let groups = parse_obj_groups(text)?;
+        let any_uvs = groups.iter().any(|group| !group.mesh.uvs.is_empty());
+        let any_normals = groups.iter().any(|group| !group.mesh.normals.is_empty());
+        for group in groups {
+            let mesh = group.mesh;
+            let offset = merged.verts.len();
+            merged.tris.extend(mesh.tris.iter().map(|index| index + offset));
+            if any_uvs {
+                if mesh.uvs.is_empty() {
+                    merged.uvs.resize(offset + mesh.verts.len(), TexCoord{u:0.0, v:0.0});
+                } else {
+                    merged.uvs.extend(mesh.uvs);
+                }
+            }
+            if any_normals {
+                if mesh.normals.is_empty() {
+                    merged.normals.resize(offset + mesh.verts.len(), Vert3{x:0.0, y:0.0, z:0.0});
+                } else {
+                    merged.normals.extend(mesh.normals);
+                }
+            }
+            merged.verts.extend(mesh.verts);
+        }
+        Ok(merged)
+    }
+}
+
+#[test]
+fn parse_obj_test() {
+    let text = "
+# unit square split in two groups, one vertex has a color
+v 0 0 0
+v 1 0 0
+v 1 1 0 1 0 0.5
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vn 0 0 1
+o square
+f 1/1/1 2/2/1 3/3/1 4//1
+g tail
+f -4 -2 -1   # negative indices count back from the last vertex
+usemtl ignored
+s off
+";
+    let groups = parse_obj_groups(text).unwrap();
+    assert_eq!(groups.len(), 2);
+
+    // quad becomes a two triangle fan, shared corners are shared verts, each triangle's winding
+    // is reversed along with the z mirroring
+    let square = &groups[0];
+    assert_eq!(square.name, "square");
+    assert_eq!(square.mesh.tris, vec![0, 2, 1, 0, 3, 2]);
+    assert_eq!(square.mesh.verts.len(), 4);
+    assert_eq!(square.mesh.uvs[2], TexCoord{u:1.0, v:1.0});
+    assert_eq!(square.mesh.uvs[3], TexCoord{u:0.0, v:0.0}); // corner without a uv
+    assert!(square.mesh.normals.iter().all(|normal| *normal == Vert3{x:0.0, y:0.0, z:-1.0}));
+
+    let tail = &groups[1];
+    assert_eq!(tail.name, "tail");
+    assert_eq!(tail.mesh.verts, vec![Vert3{x:0.0, y:0.0, z:0.0}, Vert3{x:1.0, y:1.0, z:0.0}, Vert3{x:0.0, y:1.0, z:0.0}]);
+    assert!(tail.mesh.uvs.is_empty() && tail.mesh.normals.is_empty());
+
+    // merged mesh keeps every triangle, attributes line up with the verts
+    let merged = Mesh::from_obj(text).unwrap();
+    assert_eq!(merged.tris, vec![0, 2, 1, 0, 3, 2, 4, 6, 5]);
+    assert_eq!(merged.uvs.len(), merged.verts.len());
+    assert_eq!(merged.normals.len(), merged.verts.len());
+
+    // right handed obj space has +z towards the viewer, ours has it going away
+    let tilted = Mesh::from_obj("v 0 0 1\nv 1 0 2\nv 0 1 3\nf 1 2 3").unwrap();
+    assert_eq!(tilted.verts.iter().map(|vert| vert.z).collect::<Vec<f32>>(), vec![-1.0, -2.0, -3.0]);
+}
+
+#[test]
+fn parse_obj_error_test() {
+    let error = |text:&str| Mesh::from_obj(text).err().unwrap();
+    assert_eq!(error("v 0 0 0\nv 1 0 0\nf 1 2 3").line, 3);
+    assert_eq!(error("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 0").message, "vertex index 0 is out of range, 3 defined so far");
+    assert_eq!(error("v 0 0\n").message, "vertex needs 3 to 7 numbers, found 2");
+    assert_eq!(error("vn 0 0 1 1\n").message, "normal needs 3 numbers, found 4");
+    assert_eq!(error("v 0 zero 0\n").message, "vertex has \"zero\" where a number should be");
+    assert_eq!(error("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1/1 2 3").message, "texture coordinate index 1 is out of range, 0 defined so far");
+    assert_eq!(error("v 0 0 0\nf 1 1").message, "face needs at least 3 corners, found 2");
+    assert_eq!(error("v 0 0 0\nf 1 1 -2").to_string(), "obj line 2: vertex index -2 is out of range, 1 defined so far");
+}
@@ -0,0 +1,55 @@
+use std::fmt;
+
+use crate::scene::texture::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PngError {
+    pub message:String
+}
+impl fmt::Display for PngError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "png: {}", self.message)
+    }
+}
+impl std::error::Error for PngError {}
+
+impl Texture {
+    // any png the decoder understands, turned into 8 bit rgba, only the first frame of an animated one is used
+    pub fn from_png(bytes:&[u8]) -> Result<Self, PngError> {
+        let error = |message:String| PngError { message };
+        let mut decoder = png::Decoder::new(bytes);
+        // palettes, low bit depths and transparency chunks are expanded, 16 bit channels cut down to 8
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|decode_error| error(decode_error.to_string()))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut buffer).map_err(|decode_error| error(decode_error.to_string()))?;
+        let texels = &buffer[..frame.buffer_size()];
+
+        let pixels:Vec<u8> = match frame.color_type {
+            png::ColorType::Rgba => texels.to_vec(),
+            png::ColorType::Rgb => texels.chunks_exact(3).flat_map(|texel| [texel[0], texel[1], texel[2], 255]).collect(),
+            png::ColorType::GrayscaleAlpha => texels.chunks_exact(2).flat_map(|texel| [texel[0], texel[0], texel[0], texel[1]]).collect(),
+            png::ColorType::Grayscale => texels.iter().flat_map(|&grey| [grey, grey, grey, 255]).collect(),
+            png::ColorType::Indexed => return Err(error("palette wasn't expanded".to_string()))
+        };
+        Texture::from_rgba(frame.width as usize, frame.height as usize, pixels).map_err(|texture_error| error(texture_error.message))
+    }
+}
+
+#[test]
+fn png_test() {
+    // 2x1 grey and alpha image written with the same crate, read back as rgba
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+        encoder.set_color(png::ColorType::GrayscaleAlpha);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[10, 255, 200, 128]).unwrap();
+    }
+    let texture = Texture::from_png(&bytes).unwrap();
+    assert_eq!((texture.width, texture.height), (2, 1));
+    assert_eq!(texture.pixels, vec![10, 10, 10, 255, 200, 200, 200, 128]);
+
+    assert!(Texture::from_png(b"P6 1 1 255 abc").is_err());
+}
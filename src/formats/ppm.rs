@@ -0,0 +1,123 @@
+use std::fmt;
+
+use crate::scene::texture::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PpmError {
+    pub message:String
+}
+impl fmt::Display for PpmError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ppm: {}", self.message)
+    }
+}
+impl std::error::Error for PpmError {}
+
+// reads the whitespace separated fields of the header, skipping # comments
+struct HeaderReader<'a> {
+    bytes:&'a [u8],
+    position:usize
+}
+impl HeaderReader<'_> {
+    fn next_field(&mut self) -> Option<&str> {
+        loop {
+            match self.bytes.get(self.position)? {
+                byte if byte.is_ascii_whitespace() => self.position += 1,
+                b'#' => {
+                    while self.bytes.get(self.position).is_some_and(|&byte| byte != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                _ => break
+            }
+        }
+        let start = self.position;
+        while self.bytes.get(self.position).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.position]).ok()
+    }
+
+    fn next_number(&mut self, what:&str) -> Result<usize, PpmError> {
+        match self.next_field() {
+            Some(field) => field.parse().map_err(|_| PpmError { message: format!("{} is \"{}\", not a whole number", what, field) }),
+            None => Err(PpmError { message: format!("file ends before the {}", what) })
+        }
+    }
+}
+
+impl Texture {
+    // binary (P6) or ascii (P3) ppm, channels are scaled from the file's max value to 0..255
+    pub fn from_ppm(bytes:&[u8]) -> Result<Self, PpmError> {
+        let error = |message:String| Err(PpmError { message });
+        let mut header = HeaderReader { bytes, position: 0 };
+        let binary = match header.next_field() {
+            Some("P6") => true,
+            Some("P3") => false,
+            _ => return error("doesn't start with P6 or P3".to_string())
+        };
+        let width = header.next_number("width")?;
+        let height = header.next_number("height")?;
+        let max_value = header.next_number("max value")?;
+        if max_value == 0 || max_value > 65535 {
+            return error(format!("max value {} is outside 1..65535", max_value));
+        }
+
+        let size = if max_value > 255 { 2 } else { 1 };
+        // sizes come straight from the header, so they are checked before anything is allocated or sliced
+        let (channel_count, byte_count) = match width.checked_mul(height).and_then(|texels| texels.checked_mul(3)) {
+            Some(channel_count) if channel_count.checked_mul(size).is_some() => (channel_count, channel_count * size),
+            _ => return error(format!("{}x{} image is too big", width, height))
+        };
+        let channels:Vec<usize> = if binary {
+            // a single whitespace byte separates the header from the texels, 2 bytes big endian per channel past 255
+            let start = header.position + 1;
+            match start.checked_add(byte_count).and_then(|end| bytes.get(start..end)) {
+                Some(data) if size == 1 => data.iter().map(|&value| value as usize).collect(),
+                Some(data) => data.chunks_exact(2).map(|value| u16::from_be_bytes([value[0], value[1]]) as usize).collect(),
+                None => return error(format!("{}x{} image needs {} bytes of texels, found {}", width, height, byte_count, bytes.len().saturating_sub(start)))
+            }
+        } else {
+            // every ascii value takes at least a byte, so there can't be more of them than bytes left in the file
+            let mut channels = Vec::with_capacity(channel_count.min(bytes.len() - header.position));
+            for _ in 0..channel_count {
+                channels.push(header.next_number("texel")?);
+            }
+            channels
+        };
+        if let Some(value) = channels.iter().find(|&&value| value > max_value) {
+            return error(format!("texel value {} is over the max value {}", value, max_value));
+        }
+
+        let pixels = channels.chunks_exact(3)
+            .flat_map(|texel| [texel[0], texel[1], texel[2], max_value].map(|value| ((value * 255 + max_value / 2) / max_value) as u8))
+            .collect();
+        Texture::from_rgba(width, height, pixels).map_err(|texture_error| PpmError { message: texture_error.message })
+    }
+}
+
+#[test]
+fn ppm_test() {
+    let ascii = Texture::from_ppm(b"P3\n# 2x1, red then half grey\n2 1\n15\n15 0 0  7 7 7\n").unwrap();
+    assert_eq!((ascii.width, ascii.height), (2, 1));
+    assert_eq!(ascii.pixels, vec![255, 0, 0, 255, 119, 119, 119, 255]);
+
+    let mut binary = b"P6 1 2 255\n".to_vec();
+    binary.extend_from_slice(&[1, 2, 3, 250, 251, 252]);
+    assert_eq!(Texture::from_ppm(&binary).unwrap().pixels, vec![1, 2, 3, 255, 250, 251, 252, 255]);
+
+    let mut wide = b"P6 1 1 65535 ".to_vec();
+    wide.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+    assert_eq!(Texture::from_ppm(&wide).unwrap().pixels, vec![255, 128, 0, 255]);
+
+    let error = |bytes:&[u8]| Texture::from_ppm(bytes).err().unwrap().message;
+    assert_eq!(error(b"P5 1 1 255 a"), "doesn't start with P6 or P3");
+    assert_eq!(error(b"P3 1 1 255 0 0 300"), "texel value 300 is over the max value 255");
+    assert_eq!(error(b"P3 1 x"), "height is \"x\", not a whole number");
+    assert_eq!(error(b"P6 2 1 255 abc"), "2x1 image needs 6 bytes of texels, found 3");
+
+    // sizes that overflow are errors rather than panics
+    assert_eq!(error(b"P6 4294967296 4294967296 255 abc"), "4294967296x4294967296 image is too big");
+    assert_eq!(error(format!("P6 {} 1 65535 abc", usize::MAX / 3).as_bytes()), format!("{}x1 image is too big", usize::MAX / 3));
+    assert_eq!(error(b"P3 70000 70000 255 1 2 3"), "file ends before the texel");
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::scene::mesh::*;
+
+const BINARY_HEADER_SIZE:usize = 80;
+const BINARY_TRIANGLE_SIZE:usize = 50; // normal, 3 corners and a 2 byte attribute count
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StlError {
+    pub message:String
+}
+impl fmt::Display for StlError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "stl: {}", self.message)
+    }
+}
+impl std::error::Error for StlError {}
+
+// joins corners at exactly the same position into one vert, stl repeats them for every facet
+struct Welder {
+    mesh:Mesh,
+    vert_lookup:HashMap<[u32; 3], usize>
+}
+impl Welder {
+    fn new() -> Self {
+        Self { mesh: Mesh::new(Vec::new(), Vec::new()), vert_lookup: HashMap::new() }
+    }
+    fn add_triangle(&mut self, corners:[Vert3; 3]) {
+        for corner in corners {
+            // + 0.0 turns -0.0 into 0.0 so they weld together
+            let key = [(corner.x + 0.0).to_bits(), (corner.y + 0.0).to_bits(), (corner.z + 0.0).to_bits()];
+            let next_index = self.mesh.verts.len();
+            let index = *self.vert_lookup.entry(key).or_insert(next_index);
+            if index == next_index {
+                self.mesh.verts.push(corner);
+            }
+            self.mesh.tris.push(index);
+        }
+    }
+}
+
+// binary files are recognised by their size matching the triangle count in the header,
+// since plenty of binary exporters also start the header with "solid"
+fn is_binary(bytes:&[u8]) -> bool {
+    match bytes.get(BINARY_HEADER_SIZE..BINARY_HEADER_SIZE + 4) {
+        Some(count) => {
+            // a count too big to size up on 32 bit targets can't be a binary file we were handed either
+            let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+            count.checked_mul(BINARY_TRIANGLE_SIZE).and_then(|size| size.checked_add(BINARY_HEADER_SIZE + 4)) == Some(bytes.len())
+        }
+        None => false
+    }
+}
+
+fn parse_binary(bytes:&[u8]) -> Mesh {
+    let read_f32 = |offset:usize| f32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+    let read_vert = |offset:usize| Vert3{x:read_f32(offset), y:read_f32(offset + 4), z:read_f32(offset + 8)};
+
+    let mut welder = Welder::new();
+    for triangle in bytes[BINARY_HEADER_SIZE + 4..].chunks_exact(BINARY_TRIANGLE_SIZE).enumerate() {
+        // facet normal at the start of each triangle is skipped, it is implied by the winding
+        let offset = BINARY_HEADER_SIZE + 4 + triangle.0 * BINARY_TRIANGLE_SIZE + 12;
+        welder.add_triangle([read_vert(offset), read_vert(offset + 12), read_vert(offset + 24)]);
+    }
+    welder.mesh
+}
+
+fn parse_ascii(text:&str) -> Result<Mesh, StlError> {
+    let error = |line:usize, message:String| Err(StlError { message: format!("line {}: {}", line, message) });
+
+    let mut welder = Welder::new();
+    let mut corners:Vec<Vert3> = Vec::with_capacity(3);
+    let mut in_solid = false;
+    let mut in_loop = false;
+    for (line_index, raw_line) in text.lines().enumerate() {
+        let line = line_index + 1;
+        let fields:Vec<&str> = raw_line.split_whitespace().collect();
+        match fields.first().copied() {
+            None => continue,
+            Some("solid") if !in_solid => in_solid = true,
+            Some("endsolid") if in_solid && !in_loop => in_solid = false,
+            Some("facet") | Some("endfacet") if in_solid && !in_loop => {} // facet normals are implied by the winding
+            Some("outer") if in_solid && !in_loop => {
+                in_loop = true;
+                corners.clear();
+            }
+            Some("vertex") if in_loop => {
+                if fields.len() != 4 {
+                    return error(line, format!("vertex needs 3 numbers, found {}", fields.len() - 1));
+                }
+                let mut coords = [0.0; 3];
+                for (coord, field) in coords.iter_mut().zip(&fields[1..]) {
+                    *coord = match field.parse() {
+                        Ok(value) => value,
+                        Err(_) => return error(line, format!("vertex has \"{}\" where a number should be", field))
+                    };
+                }
+                corners.push(Vert3{x:coords[0], y:coords[1], z:coords[2]});
+            }
+            Some("endloop") if in_loop => {
+                if corners.len() != 3 {
+                    return error(line, format!("facet has {} vertices, stl facets are triangles", corners.len()));
+                }
+                welder.add_triangle([corners[0], corners[1], corners[2]]);
+                in_loop = false;
+            }
+            Some(keyword) => return error(line, format!("unexpected \"{}\"", keyword))
+        }
+    }
+    if in_solid {
+        return Err(StlError { message: "text ends before \"endsolid\"".to_string() });
+    }
+    Ok(welder.mesh)
+}
+
+impl Mesh {
+    // reads ascii or binary stl, corners shared between facets become shared verts
+    pub fn from_stl(bytes:&[u8]) -> Result<Self, StlError> {
+        let mut mesh = if is_binary(bytes) {
+            parse_binary(bytes)
+        } else {
+            match std::str::from_utf8(bytes) {
+                Ok(text) if text.trim_start().starts_with("solid") => parse_ascii(text)?,
+                _ => return Err(StlError { message: "not ascii stl and the size doesn't match the binary triangle count".to_string() })
+            }
+        };
+        // stl facets are counter clockwise in a right handed space, mirroring z makes it our left handed one
+        mesh.flip_handedness();
+        Ok(mesh)
+    }
+
+    // binary stl with a facet normal worked out for every triangle, mirrored back into stl's right handed space
+    // the same way flip_handedness does it so from_stl reads back the mesh we started with
+    pub fn to_stl_binary(&self) -> Vec<u8> {
+        let triangle_count = self.tris.len() / 3;
+        let mut bytes = Vec::with_capacity(BINARY_HEADER_SIZE + 4 + triangle_count * BINARY_TRIANGLE_SIZE);
+        let mut header = b"binary stl".to_vec();
+        header.resize(BINARY_HEADER_SIZE, b' ');
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&(triangle_count as u32).to_le_bytes());
+        for (tri, normal) in self.tris.chunks_exact(3).zip(self.face_normals()) {
+            for vert in [normal, self.verts[tri[0]], self.verts[tri[2]], self.verts[tri[1]]] {
+                for coord in [vert.x, vert.y, -vert.z] {
+                    bytes.extend_from_slice(&coord.to_le_bytes());
+                }
+            }
+            bytes.extend_from_slice(&0u16.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+#[test]
+fn ascii_stl_test() {
+    let text = "solid square
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 1 1 0
+    endloop
+  endfacet
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 -0
+      vertex 1 1 0
+      vertex 0 1 0
+    endloop
+  endfacet
+endsolid square
+";
+    let mesh = Mesh::from_stl(text.as_bytes()).unwrap();
+    assert_eq!(mesh.verts.len(), 4); // the shared diagonal and the -0 corner are welded
+    assert_eq!(mesh.tris, vec![0, 2, 1, 0, 3, 2]); // wound the other way round with z mirrored
+
+    let error = |text:&str| Mesh::from_stl(text.as_bytes()).err().unwrap().message;
+    assert_eq!(error("solid x\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop"), "line 6: facet has 2 vertices, stl facets are triangles");
+    assert_eq!(error("solid x\nfacet normal 0 0 1\nouter loop\nvertex 0 zero 0"), "line 4: vertex has \"zero\" where a number should be");
+    assert_eq!(error("solid x\nfacet normal 0 0 1\n"), "text ends before \"endsolid\"");
+    assert!(error("ply\n").starts_with("not ascii stl"));
+}
+
+#[test]
+fn binary_stl_round_trip_test() {
+    let cube = Mesh::cube(2.0);
+    let bytes = cube.to_stl_binary();
+    assert_eq!(bytes.len(), 84 + 12 * 50);
+
+    // the first facet normal points out of the cube, both of them seen in the file's mirrored space
+    let read_f32 = |offset:usize| f32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+    let normal = Vert3{x:read_f32(84), y:read_f32(88), z:read_f32(92)};
+    let first = Vert3{x:read_f32(96), y:read_f32(100), z:read_f32(104)};
+    assert_eq!(first, Vert3{z:-cube.verts[cube.tris[0]].z, ..cube.verts[cube.tris[0]]});
+    assert!(normal.x * first.x + normal.y * first.y + normal.z * first.z > 0.0);
+    assert!(((normal.x * normal.x + normal.y * normal.y + normal.z * normal.z) - 1.0).abs() < 0.0001);
+
+    // reading it back welds the 36 corners into the cube's 8 verts, triangles in the same order
+    let loaded = Mesh::from_stl(&bytes).unwrap();
+    assert_eq!(loaded.verts.len(), 8);
+    let corners = |mesh:&Mesh| mesh.tris.iter().map(|&index| mesh.verts[index]).collect::<Vec<_>>();
+    assert_eq!(corners(&loaded), corners(&cube));
+}
+
+#[test]
+fn binary_stl_size_test() {
+    // a triangle count whose size overflows on 32 bit targets, and one that doesn't match the file's size
+    let mut bytes = vec![0u8; 84];
+    bytes[80..84].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(!is_binary(&bytes));
+    bytes[80..84].copy_from_slice(&1u32.to_le_bytes());
+    assert!(!is_binary(&bytes));
+    bytes.resize(84 + 50, 0);
+    assert!(is_binary(&bytes));
+}
@@ -0,0 +1,70 @@
+use nalgebra::Matrix3x2;
+
+use crate::clipping::rect::ClipRect;
+
+// color and depth buffers for a canvas whose size is only known at runtime
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub color: Vec<u8>, // 4 u8 values for each pixel, rgba, rows top to bottom
+    pub depth: Vec<f32> // one value per pixel, the renderer's clear depth where nothing was drawn
+}
+impl Framebuffer {
+    pub fn new(width:usize, height:usize, clear_depth:f32) -> Self {
+        let mut framebuffer = Self { width: 0, height: 0, color: Vec::new(), depth: Vec::new() };
+        framebuffer.resize(width, height, clear_depth);
+        framebuffer
+    }
+
+    // reallocates both buffers for the new size, contents are cleared with depth set to clear_depth, at least 1x1
+    pub fn resize(&mut self, width:usize, height:usize, clear_depth:f32) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.color = vec![255; self.width * self.height * 4];
+        self.depth = vec![clear_depth; self.width * self.height];
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    // width over height, feeds the projection so non square canvases are not stretched
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    // rect covering every pixel, what lines get clipped to before being drawn
+    pub fn clip_rect(&self) -> ClipRect {
+        ClipRect::from_canvas_size(self.width, self.height)
+    }
+
+    // matrix transform to canvas space, maps -1..1 on both axes to the full canvas
+    pub fn canvas_coord_transform(&self) -> Matrix3x2<f32> {
+        let half_width = self.width as f32 / 2.0;
+        let half_height = self.height as f32 / 2.0;
+        Matrix3x2::new(
+            half_width,      0.0    ,
+               0.0    , -half_height,
+            half_width,  half_height
+        )
+    }
+}
+
+#[test]
+fn framebuffer_resize_test() {
+    let mut framebuffer = Framebuffer::new(640, 360, f32::MAX);
+    assert_eq!(framebuffer.color.len(), 640 * 360 * 4);
+    assert_eq!(framebuffer.depth.len(), 640 * 360);
+    assert!((framebuffer.aspect_ratio() - 16.0 / 9.0).abs() < 0.0001);
+
+    // corners of normalized device space land on the canvas corners
+    let transform = framebuffer.canvas_coord_transform();
+    let to_canvas = |x:f32, y:f32| (x * transform.m11 + y * transform.m21 + transform.m31, x * transform.m12 + y * transform.m22 + transform.m32);
+    assert_eq!(to_canvas(-1.0, 1.0), (0.0, 0.0));
+    assert_eq!(to_canvas(1.0, -1.0), (640.0, 360.0));
+
+    framebuffer.resize(0, 20, 0.0);
+    assert_eq!((framebuffer.width, framebuffer.height), (1, 20));
+    assert_eq!(framebuffer.color.len(), 20 * 4);
+    assert!(framebuffer.depth.iter().all(|&depth| depth == 0.0));
+}
@@ -0,0 +1,1115 @@
+use nalgebra::{Matrix4, Vector3};
+use wasm_bindgen::prelude::*;
+
+use crate::clipping::frustum::*;
+use crate::clipping::rect::*;
+use crate::color::*;
+use crate::framebuffer::Framebuffer;
+use crate::scene::camera::Camera;
+use crate::scene::fog::Fog;
+use crate::scene::light::*;
+use crate::scene::material::Material;
+use crate::scene::mesh::*;
+use crate::scene::texture::{Texture, TextureFilter};
+use crate::scene::Scene;
+use crate::transformations::make_normal_matrix;
+
+// wireframe lines drawn over filled triangles are pulled towards the camera by this fraction of their depth
+// so they win the depth test against the face they outline
+const WIREFRAME_DEPTH_BIAS:f32 = 0.01;
+
+// how triangles are drawn by render_scene_to_buffer
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum RenderMode {
+    Wireframe,
+    Filled,
+    FilledWireframe
+}
+
+// how filled triangles are lit when the scene has lights, without any they are drawn in their material's base color
+// flat and gouraud only light the base color, blinn-phong adds the material's highlights
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Shading {
+    Flat, // one shade per face, lit at its center
+    Gouraud, // lit at each vert and blended across the face, needs vertex normals so smooth ones are made up if the mesh has none
+    BlinnPhong // normals and positions blended across the face and lit at every pixel, vertex normals as for gouraud
+}
+
+// what a mesh's filled triangles are colored by
+pub struct Surface<'a> {
+    pub material:&'a Material,
+    pub texture:Option<&'a Texture>, // only given when the mesh has uvs
+    pub lights:&'a [PlacedLight], // view space, nothing lights the triangles when empty
+    pub orthographic:bool, // the eye looks down +z from everywhere rather than out of the origin
+    pub wireframe_color:Color // what the outlines are drawn in
+}
+impl Surface<'_> {
+    // true when triangles have to be worked out pixel by pixel rather than blending colors from their corners
+    fn per_pixel(&self, shading:Shading) -> bool {
+        self.texture.is_some() || (shading == Shading::BlinnPhong && !self.lights.is_empty())
+    }
+
+    // base color with the texture at uv multiplied in, 0..1 for each channel, lod picks the mip level for trilinear filtering
+    fn base_color(&self, uv:&[f32; 2], lod:f32) -> [f32; 3] {
+        let base = [self.material.base_color.0, self.material.base_color.1, self.material.base_color.2];
+        match self.texture {
+            Some(texture) => {
+                let texel = texture.sample(TexCoord{u:uv[0], v:uv[1]}, self.material.texture_filter, lod);
+                [0, 1, 2].map(|channel| base[channel] * texel[channel])
+            }
+            None => base
+        }
+    }
+
+    // color (0..255 for each channel) of a pixel facing along normal at the view space position,
+    // the normal is brought back to unit length here as interpolation shortens it
+    fn blinn_phong_color(&self, position:Vector3<f32>, normal:Vector3<f32>, base:[f32; 3]) -> [f32; 3] {
+        let normal = normal.try_normalize(0.0).unwrap_or(normal);
+        let to_eye = if self.orthographic { Vector3::new(0.0, 0.0, -1.0) } else { (-position).try_normalize(0.0).unwrap_or_else(Vector3::zeros) };
+        let (diffuse, specular) = blinn_phong_lighting(self.lights, position, normal, to_eye, self.material.shininess);
+        let material = self.material;
+        let channels = [
+            (material.specular_color.0, material.emissive.0),
+            (material.specular_color.1, material.emissive.1),
+            (material.specular_color.2, material.emissive.2)
+        ];
+        [0, 1, 2].map(|channel| {
+            let (specular_color, emissive) = channels[channel];
+            ((base[channel] * diffuse[channel] + specular_color * specular[channel] + emissive) * 255.0).clamp(0.0, 255.0)
+        })
+    }
+
+    // color (0..255 for each channel) of the base color lit by shade
+    fn shaded_color(&self, shade:&[f32; 3], base:[f32; 3]) -> [f32; 3] {
+        let emissive = [self.material.emissive.0, self.material.emissive.1, self.material.emissive.2];
+        [0, 1, 2].map(|channel| ((base[channel] * shade[channel] + emissive[channel]) * 255.0).clamp(0.0, 255.0))
+    }
+}
+
+// how lines and wireframe outlines are drawn
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum LineMode {
+    Aliased, // one whole pixel per step, bresenham, jagged when not horizontal, vertical or diagonal
+    AntiAliased // xiaolin wu, the two pixels either side of the line share it by how much of it they cover
+}
+
+// compare function used by the depth test, a pixel is drawn when "new_z <func> stored_z" holds
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum DepthFunc {
+    Less,
+    LessEqual,
+    Always,
+    Never,
+    Greater
+}
+impl DepthFunc {
+    pub fn passes(self, new_z:f32, stored_z:f32) -> bool {
+        match self {
+            DepthFunc::Less      => new_z <  stored_z,
+            DepthFunc::LessEqual => new_z <= stored_z,
+            DepthFunc::Always    => true,
+            DepthFunc::Never     => false,
+            DepthFunc::Greater   => new_z >  stored_z
+        }
+    }
+}
+
+// which faces get skipped by draw_triangle
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum CullMode {
+    None,
+    Back,
+    Front
+}
+
+// winding of front facing triangles as they appear on the canvas
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum FrontFace {
+    Clockwise,
+    CounterClockwise
+}
+
+pub struct Line2d_i(pub ( i16,  i16), pub ( i16,  i16));
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Line2d_u(pub ( usize,  usize), pub ( usize,  usize));
+
+
+// a when factor is 0, b when it is 1
+pub fn mix_values(a:f32, b:f32, factor:f32) -> f32{
+    a * (1.0 - factor) + b * factor
+}
+
+// the weights are even steps across the canvas, but the verts' attributes (depth included) are even steps across
+// the triangle in view space, dividing by w before blending and by the blended 1 / w after undoes the perspective
+fn perspective_weights((w_a, w_b, w_c):(f32, f32, f32), (inv_w_a, inv_w_b, inv_w_c):(f32, f32, f32)) -> (f32, f32, f32) {
+    let (p_a, p_b, p_c) = (w_a * inv_w_a, w_b * inv_w_b, w_c * inv_w_c);
+    let inv_sum = 1.0 / (p_a + p_b + p_c);
+    (p_a * inv_sum, p_b * inv_sum, p_c * inv_sum)
+}
+
+// signed area (times two) of the parallelogram spanned by a->b and a->p
+// positive on one side of the edge a->b, negative on the other, zero on the edge itself
+fn edge_function(a:(f32, f32), b:(f32, f32), p:(f32, f32)) -> f32 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+// walks every pixel center inside the triangle (canvas space, float pixel coords) and hands
+// the pixel coords along with the barycentric weights of a, b and c to the plot function
+// works for either winding, pixels outside of the width x height canvas are never visited
+pub fn rasterize_triangle<F>(a:(f32, f32), b:(f32, f32), c:(f32, f32), width:usize, height:usize, mut plot:F)
+where F: FnMut(usize, usize, f32, f32, f32) {
+    let area = edge_function(a, b, c);
+    if area == 0.0 || !area.is_finite() {
+        return; // degenerate triangle covers no pixels
+    }
+
+    // bounding box of the triangle, clamped to the canvas
+    let min_x = a.0.min(b.0).min(c.0).floor().max(0.0);
+    let min_y = a.1.min(b.1).min(c.1).floor().max(0.0);
+    let max_x = a.0.max(b.0).max(c.0).ceil().min(width as f32 - 1.0);
+    let max_y = a.1.max(b.1).max(c.1).ceil().min(height as f32 - 1.0);
+    if min_x > max_x || min_y > max_y {
+        return; // triangle is completely off the canvas
+    }
+
+    let inv_area = 1.0 / area;
+    for y in (min_y as usize)..=(max_y as usize) {
+        for x in (min_x as usize)..=(max_x as usize) {
+            let p = (x as f32 + 0.5, y as f32 + 0.5);
+            // dividing by the signed area makes the weights positive inside regardless of winding
+            let w_a = edge_function(b, c, p) * inv_area;
+            let w_b = edge_function(c, a, p) * inv_area;
+            let w_c = edge_function(a, b, p) * inv_area;
+            if w_a >= 0.0 && w_b >= 0.0 && w_c >= 0.0 {
+                plot(x, y, w_a, w_b, w_c);
+            }
+        }
+    }
+}
+
+// determinant of the x, y, w rows of the clip space verts, same sign as the signed area of
+// the projected triangle (positive when counter clockwise with y up) but also valid when
+// verts are behind the camera, so faces can be culled before clipping
+fn homogeneous_winding(a:&ClipVert, b:&ClipVert, c:&ClipVert) -> f32 {
+    a.x * (b.y * c.w - c.y * b.w)
+  - b.x * (a.y * c.w - c.y * a.w)
+  + c.x * (a.y * b.w - b.y * a.w)
+}
+
+// transforms a view space vert into homogeneous clip space with a perspective or orthographic matrix,
+// the perspective divide is left until after clipping
+pub fn persp_project_vert(vert:Vert3, projection_matx:Matrix4<f32>) -> ClipVert {
+    ClipVert {
+        x: vert.x * projection_matx.m11 + vert.y * projection_matx.m21 + vert.z * projection_matx.m31 + projection_matx.m41,
+        y: vert.x * projection_matx.m12 + vert.y * projection_matx.m22 + vert.z * projection_matx.m32 + projection_matx.m42,
+        z: vert.x * projection_matx.m13 + vert.y * projection_matx.m23 + vert.z * projection_matx.m33 + projection_matx.m43,
+        w: vert.x * projection_matx.m14 + vert.y * projection_matx.m24 + vert.z * projection_matx.m34 + projection_matx.m44,
+        depth: vert.z, // view space depth works for the fog pass with either projection
+        shade: [1.0; 3],
+        position: [vert.x, vert.y, vert.z],
+        normal: [0.0; 3],
+        uv: [0.0; 2]
+    }
+}
+
+// owns the buffers a frame is drawn into along with the settings used to draw it
+pub struct Renderer {
+    pub framebuffer:Framebuffer,
+    pub render_mode:RenderMode,
+    pub depth_func:DepthFunc,
+    pub depth_write:bool, // when disabled pixels are still depth tested but leave the z buffer untouched
+    pub clear_depth:f32, // what the z buffer is emptied to, 0 rather than f32::MAX lets DepthFunc::Greater keep the farthest pixels
+    pub cull_mode:CullMode,
+    pub front_face:FrontFace,
+    pub shading:Shading,
+    pub line_mode:LineMode
+}
+impl Renderer {
+    pub fn new(width:usize, height:usize) -> Self {
+        Self {
+            framebuffer: Framebuffer::new(width, height, f32::MAX),
+            render_mode: RenderMode::Wireframe,
+            depth_func: DepthFunc::Less,
+            depth_write: true,
+            clear_depth: f32::MAX,
+            // built in meshes are wound clockwise, so cull what winds the other way by default
+            cull_mode: CullMode::Back,
+            front_face: FrontFace::Clockwise,
+            shading: Shading::Gouraud,
+            line_mode: LineMode::Aliased
+        }
+    }
+
+    // reallocates the buffers to follow the size of the host canvas element
+    // the output buffer moves, so its pointer has to be fetched again afterwards
+    pub fn resize(&mut self, width:usize, height:usize) {
+        self.framebuffer.resize(width, height, self.clear_depth);
+    }
+
+    // pointer to the rgba color buffer in wasm memory
+    pub fn output_buffer_pointer(&self) -> *const u8 {
+        self.framebuffer.color.as_ptr()
+    }
+
+    // blends every drawn pixel towards the fog color by the fog at its depth in the z buffer,
+    // run after drawing the whole frame, pixels nothing was drawn to still hold the clear depth and are left alone
+    pub fn apply_fog_pass_from_z_buffer(&mut self, fog:&Fog) {
+        let clear_depth = self.clear_depth;
+        let fb = &mut self.framebuffer;
+        let fog_color = [fog.color.0, fog.color.1, fog.color.2].map(|channel| channel * 255.0);
+        for pixel in 0..fb.pixel_count() {
+            if fb.depth[pixel] != clear_depth {
+                let fogginess = fog.factor(fb.depth[pixel]);
+                for (color, fog_channel) in fb.color[pixel * 4..pixel * 4 + 3].iter_mut().zip(fog_color) {
+                    *color = mix_values(*color as f32, fog_channel, fogginess).round() as u8;
+                }
+            }
+        }
+    }
+
+    // tests z_val against the z buffer with the current depth func, writing it if enabled
+    // returns true if the pixel passed and its color should be written
+    pub fn depth_test_pixel(&mut self, x:usize, y:usize, z_val:f32) -> bool {
+        let loc_within_buffer = (y * self.framebuffer.width) + x;
+        if !self.depth_func.passes(z_val, self.framebuffer.depth[loc_within_buffer]) {
+            return false;
+        }
+        if self.depth_write {
+            self.framebuffer.depth[loc_within_buffer] = z_val;
+        }
+        true
+    }
+
+    // fills the canvas with the background and empties the z buffer, useful for clearing before each frame is drawn
+    pub fn clear_frame_buffer(&mut self, background:&Background){
+        let fb = &mut self.framebuffer;
+        for (y, row) in fb.color.chunks_exact_mut(fb.width * 4).enumerate() {
+            let color = background.row_color(y, fb.height).to_array();
+            for pixel in row.chunks_exact_mut(4) {
+                pixel.copy_from_slice(&color);
+            }
+        }
+        fb.depth.fill(self.clear_depth);
+    }
+
+    pub fn put_buffer_pixel(&mut self, x:usize, y:usize, red: u8, green: u8, blue: u8, alpha: u8){    
+        let loc_within_buffer = (y * self.framebuffer.width + x) * 4;
+        self.framebuffer.color[loc_within_buffer    ] = red;
+        self.framebuffer.color[loc_within_buffer + 1] = green;
+        self.framebuffer.color[loc_within_buffer + 2] = blue;
+        self.framebuffer.color[loc_within_buffer + 3] = alpha;
+    }
+
+    // depth tests the pixel and writes the color if it passed, see through colors are blended over what is there
+    fn put_depth_tested_pixel(&mut self, x:usize, y:usize, z_val:f32, color:Color) {
+        if self.depth_test_pixel(x, y, z_val) {
+            let loc_within_buffer = (y * self.framebuffer.width + x) * 4;
+            let pixel = &mut self.framebuffer.color[loc_within_buffer..loc_within_buffer + 4];
+            let color = if color.a == 255 { color } else { color.over(Color::from_array([pixel[0], pixel[1], pixel[2], pixel[3]])) };
+            pixel.copy_from_slice(&color.to_array());
+        }
+    }
+
+    // start and end are whole pixel coords already on the canvas, start left of or level with end
+    pub fn draw_clamped_line_to_buffer(&mut self, start:&ClipPoint2d, end:&ClipPoint2d, color:Color) {
+        let (x0, y0, x1, y1) = (start.x as usize, start.y as usize, end.x as usize, end.y as usize);
+        // one step per pixel along the major axis
+        let steps = (x1 - x0).max(y0.abs_diff(y1));
+        let depth_at = |step:usize| if steps > 0 { start.lerp(end, step as f32 / steps as f32).z } else { start.z };
+
+        if x0 != x1 && y0 != y1{
+            // if line is not horizontal or vertical
+            //https://en.wikipedia.org//wiki/Bresenham's_line_algorithm#Derivation
+            let abs_delta_x:i16 = (x1 - x0) as i16; // x1 guaranteed to be bigger than x0 since that is part of clamping
+            let sign_of_x_delta:i16 = 1; // sign of x 
+
+            let neg_delta_y:i16 = -(y1 as i16 - y0 as i16).abs();
+            let sign_of_y_delta:i16 = if y0 < y1 { 1 } else { -1 };
+
+            let mut error = abs_delta_x + neg_delta_y;
+
+            let mut curr_x:i16 = x0 as i16;
+            let mut curr_y:i16 = y0 as i16;
+            let x_end:i16 = x1 as i16;
+            let y_end:i16 = y1 as i16;
+
+            
+            let mut step = 0;
+            loop {
+                self.put_depth_tested_pixel(curr_x as usize, curr_y as usize, depth_at(step), color);
+                
+                if curr_x == x_end && curr_y == y_end { break; }
+                step += 1;
+
+                let e2 = 2 * error;
+                
+                if e2 >= neg_delta_y {
+                    if curr_x == x_end { break }
+                    error += neg_delta_y;
+                    curr_x += sign_of_x_delta;
+                }
+                if e2 <= abs_delta_x {
+                    if curr_y == y_end { break }
+                    error += abs_delta_x;
+                    curr_y += sign_of_y_delta;
+                }
+            }
+        } else if x0 == x1 {
+            // if vertical (or single point)
+            let min_y = if y0 > y1 { y1 } else { y0 };
+            let max_y = if y0 > y1 { y0 } else { y1 };
+            for curr_y in min_y..max_y {
+                // y may be walked against the direction of the line, so derive z from the distance to y0
+                self.put_depth_tested_pixel(x0, curr_y, depth_at(curr_y.abs_diff(y0)), color);
+            }
+        } else {
+            // we have x0 < x1 guarantee
+            for curr_x in x0..x1 {
+                self.put_depth_tested_pixel(curr_x, y0, depth_at(curr_x - x0), color);
+            }
+        }
+    }
+
+    // xiaolin wu's line between canvas space points, pixel centers on whole coords like the aliased lines,
+    // every pixel's share of the line scales the color's alpha before it is blended in, and is depth tested
+    // at the depth part way along the line like a whole pixel would be
+    pub fn draw_antialiased_line_to_buffer(&mut self, start:&ClipPoint2d, end:&ClipPoint2d, color:Color) {
+        // walk along the longer axis, stepping across the shorter one by gradient each pixel
+        let steep = (end.y - start.y).abs() > (end.x - start.x).abs();
+        let (start, end) = if (steep && start.y > end.y) || (!steep && start.x > end.x) { (end, start) } else { (start, end) };
+        let (x0, y0, x1, y1) = if steep { (start.y, start.x, end.y, end.x) } else { (start.x, start.y, end.x, end.y) };
+        let length = x1 - x0;
+        let gradient = if length == 0.0 { 1.0 } else { (y1 - y0) / length };
+        let depth_at = |x:f32| if length == 0.0 { start.z } else { start.lerp(end, ((x - x0) / length).clamp(0.0, 1.0)).z };
+
+        let (width, height) = (self.framebuffer.width as i64, self.framebuffer.height as i64);
+        let plot = |renderer:&mut Self, along:i64, across:i64, coverage:f32| {
+            let (x, y) = if steep { (across, along) } else { (along, across) };
+            let alpha = (color.a as f32 * coverage).round() as u8;
+            if alpha == 0 || x < 0 || y < 0 || x >= width || y >= height {
+                return;
+            }
+            renderer.put_depth_tested_pixel(x as usize, y as usize, depth_at(along as f32), Color { a: alpha, ..color });
+        };
+        // the pair of pixels straddling the line at a column, each covered by how close the line passes to its center
+        let plot_pair = |renderer:&mut Self, along:i64, across:f32, share:f32| {
+            let below = across.floor();
+            let fraction = across - below;
+            plot(renderer, along, below as i64, (1.0 - fraction) * share);
+            plot(renderer, along, below as i64 + 1, fraction * share);
+        };
+
+        // the end columns only get the part of the pixel the line reaches into
+        let first = (x0 + 0.5).floor();
+        let first_across = y0 + gradient * (first - x0);
+        let last = (x1 + 0.5).floor();
+        let last_across = y1 + gradient * (last - x1);
+        if first == last {
+            plot_pair(self, first as i64, (first_across + last_across) / 2.0, length.min(1.0));
+            return;
+        }
+        plot_pair(self, first as i64, first_across, 0.5 - (x0 - first));
+        plot_pair(self, last as i64, last_across, 0.5 + (x1 - last));
+        let mut across = first_across + gradient;
+        for along in (first as i64 + 1)..(last as i64) {
+            plot_pair(self, along, across, 1.0);
+            across += gradient;
+        }
+    }
+
+    // clips a segment in canvas space to the canvas and rounds it to whole pixel coords
+    // the returned endpoints are ordered left to right (x0 <= x1) along with their depth
+    fn clip_segment_to_canvas(&self, a:&ClipPoint2d, b:&ClipPoint2d) -> Option<(ClipPoint2d, ClipPoint2d)> {
+        let (start, end) = clip_segment(a, b, &self.framebuffer.clip_rect())?;
+        let start = ClipPoint2d { x: start.x.round(), y: start.y.round(), ..start };
+        let end   = ClipPoint2d { x: end.x.round(), y: end.y.round(), ..end };
+        if start.x > end.x {
+            Some((end, start))
+        } else {
+            Some((start, end))
+        }
+    }
+
+    // if any part of the line is in the canvas, return the clamped coords ordered left to right
+    // else return None
+    pub fn clamp_line_to_canvas(&self, line:&Line2d_i) -> Option<Line2d_u> {
+        let a = ClipPoint2d{ x: line.0.0 as f32, y: line.0.1 as f32, z: 0.0, w: 1.0 };
+        let b = ClipPoint2d{ x: line.1.0 as f32, y: line.1.1 as f32, z: 0.0, w: 1.0 };
+        self.clip_segment_to_canvas(&a, &b).map(|(start, end)| Line2d_u((start.x as usize, start.y as usize), (end.x as usize, end.y as usize)))
+    }
+
+    // draw a line on the canvas buffer, this is in canvas space coords, 2d pixel coords
+    //  0 . . . Width
+    //  . . . . 
+    //  . . . .
+    // Height
+    // returns false if no part of the line is on the canvas
+    pub fn draw_segment(&mut self, a:&ClipPoint2d, b:&ClipPoint2d, color:Color) -> bool {
+        if self.line_mode == LineMode::AntiAliased {
+            // wu lines start and end part way through pixels, so the ends are not rounded
+            return match clip_segment(a, b, &self.framebuffer.clip_rect()) {
+                Some((start, end)) => {
+                    self.draw_antialiased_line_to_buffer(&start, &end, color);
+                    true
+                }
+                None => false
+            };
+        }
+        match self.clip_segment_to_canvas(a, b) {
+            Some((start, end)) => {
+                self.draw_clamped_line_to_buffer(&start, &end, color);
+                true
+            }
+            None => false
+        }
+    }
+
+    pub fn draw_line(&mut self, line:&Line2d_i, start_z:f32, end_z:f32, color:Color) -> bool {
+        self.draw_segment(
+            &ClipPoint2d{ x: line.0.0 as f32, y: line.0.1 as f32, z: start_z, w: 1.0 },
+            &ClipPoint2d{ x: line.1.0 as f32, y: line.1.1 as f32, z: end_z, w: 1.0 },
+            color
+        )
+    }
+
+    // fills a triangle given in canvas space pixel coords, the depth and color of each corner are interpolated across the face
+    pub fn fill_triangle_to_buffer(&mut self, a:&ClipPoint2d, b:&ClipPoint2d, c:&ClipPoint2d, colors:[[f32; 3]; 3]) {
+        let (width, height) = (self.framebuffer.width, self.framebuffer.height);
+        let inv_w = (1.0 / a.w, 1.0 / b.w, 1.0 / c.w);
+        rasterize_triangle((a.x, a.y), (b.x, b.y), (c.x, c.y), width, height, |x, y, w_a, w_b, w_c| {
+            let [red, green, blue] = [0, 1, 2].map(|channel| (w_a * colors[0][channel] + w_b * colors[1][channel] + w_c * colors[2][channel]).round() as u8);
+            let (p_a, p_b, p_c) = perspective_weights((w_a, w_b, w_c), inv_w);
+            self.put_depth_tested_pixel(x, y, p_a * a.z + p_b * b.z + p_c * c.z, Color::rgb(red, green, blue));
+        });
+    }
+
+    // transforms a point with origin at 0 coords to pixel canvas bitmap coordinates
+    fn to_canvas_coords(&self, pt:(f32, f32)) -> (f32, f32) {
+        let transform = self.framebuffer.canvas_coord_transform();
+        (
+            pt.0 * transform.m11 + pt.1 * transform.m21 + transform.m31,
+            pt.0 * transform.m12 + pt.1 * transform.m22 + transform.m32
+        )
+    }
+
+    // takes a clipped triangle, projects its corners to canvas space coords and fills it as the surface looks under the shading mode
+    fn fill_projected_triangle(&mut self, a:&ClipVert, b:&ClipVert, c:&ClipVert, surface:&Surface) {
+        let depths = (a.depth, b.depth, c.depth);
+        let (canvas_a, canvas_b, canvas_c) = (self.to_canvas_coords(a.to_ndc()), self.to_canvas_coords(b.to_ndc()), self.to_canvas_coords(c.to_ndc()));
+        if !surface.per_pixel(self.shading) {
+            let colors = [a, b, c].map(|vert| surface.shaded_color(&vert.shade, surface.base_color(&vert.uv, 0.0)));
+            let [point_a, point_b, point_c] = [(canvas_a, a), (canvas_b, b), (canvas_c, c)].map(|(canvas, vert)| ClipPoint2d{ x: canvas.0, y: canvas.1, z: vert.depth, w: vert.w });
+            self.fill_triangle_to_buffer(&point_a, &point_b, &point_c, colors);
+            return;
+        }
+
+        let lit_per_pixel = self.shading == Shading::BlinnPhong && !surface.lights.is_empty();
+        let (width, height) = (self.framebuffer.width, self.framebuffer.height);
+        let inv_w = (1.0 / a.w, 1.0 / b.w, 1.0 / c.w);
+        let correct = |w_a:f32, w_b:f32, w_c:f32| perspective_weights((w_a, w_b, w_c), inv_w);
+        let uv_at = |(p_a, p_b, p_c):(f32, f32, f32)| [0, 1].map(|index| p_a * a.uv[index] + p_b * b.uv[index] + p_c * c.uv[index]);
+
+        // only trilinear filtering needs to know how far the uvs move from one pixel to the next, the weights
+        // change by the same amount for every step right or down the canvas
+        let mip_texture = surface.texture.filter(|_| surface.material.texture_filter == TextureFilter::Trilinear);
+        let area = edge_function(canvas_a, canvas_b, canvas_c);
+        let step_right = [canvas_b.1 - canvas_c.1, canvas_c.1 - canvas_a.1, canvas_a.1 - canvas_b.1].map(|delta| delta / area);
+        let step_down = [canvas_c.0 - canvas_b.0, canvas_a.0 - canvas_c.0, canvas_b.0 - canvas_a.0].map(|delta| delta / area);
+        rasterize_triangle(canvas_a, canvas_b, canvas_c, width, height, |x, y, w_a, w_b, w_c| {
+            let (p_a, p_b, p_c) = correct(w_a, w_b, w_c);
+            let z_val = p_a * depths.0 + p_b * depths.1 + p_c * depths.2;
+            // the depth test comes first so hidden pixels skip the texturing and lighting
+            if !self.depth_test_pixel(x, y, z_val) {
+                return;
+            }
+            let blend = |values:[&[f32; 3]; 3]| Vector3::from_fn(|index, _| p_a * values[0][index] + p_b * values[1][index] + p_c * values[2][index]);
+
+            let uv = uv_at((p_a, p_b, p_c));
+            let lod = match mip_texture {
+                Some(texture) => {
+                    // uvs at the neighbouring pixels of the 2x2 quad, carried on past the triangle's edges
+                    let right = uv_at(correct(w_a + step_right[0], w_b + step_right[1], w_c + step_right[2]));
+                    let down = uv_at(correct(w_a + step_down[0], w_b + step_down[1], w_c + step_down[2]));
+                    texture.level_of_detail([right[0] - uv[0], right[1] - uv[1]], [down[0] - uv[0], down[1] - uv[1]])
+                }
+                None => 0.0
+            };
+            let base = surface.base_color(&uv, lod);
+            let color = if lit_per_pixel {
+                surface.blinn_phong_color(blend([&a.position, &b.position, &c.position]), blend([&a.normal, &b.normal, &c.normal]), base)
+            } else {
+                let shade = blend([&a.shade, &b.shade, &c.shade]);
+                surface.shaded_color(&[shade.x, shade.y, shade.z], base)
+            };
+            let [red, green, blue] = color.map(|channel| channel.round() as u8);
+            self.put_buffer_pixel(x, y, red, green, blue, 255);
+        });
+    }
+
+    // takes a line with origin at 0 coords, projects the coords to canvas space coords and draws it
+    // depth_scale multiplies the depth written along the line
+    fn draw_projected_line(&mut self, a:&ClipVert, b:&ClipVert, depth_scale:f32, color:Color) {
+        let [a, b] = [a, b].map(|vert| {
+            let canvas = self.to_canvas_coords(vert.to_ndc());
+            ClipPoint2d{ x: canvas.0, y: canvas.1, z: vert.depth * depth_scale, w: vert.w } // z value is for the fog pass
+        });
+        self.draw_segment(&a, &b, color);
+    }
+
+    // true if the triangle is degenerate or faces away according to the cull settings
+    pub fn is_culled(&self, a:&ClipVert, b:&ClipVert, c:&ClipVert) -> bool {
+        let winding = homogeneous_winding(a, b, c);
+        if winding == 0.0 || !winding.is_finite() {
+            return true; // zero area, nothing to draw
+        }
+
+        let is_front = match self.front_face {
+            FrontFace::Clockwise        => winding < 0.0,
+            FrontFace::CounterClockwise => winding > 0.0
+        };
+        match self.cull_mode {
+            CullMode::None  => false,
+            CullMode::Back  => !is_front,
+            CullMode::Front => is_front
+        }
+    }
+
+    // clips a triangle in clip space against the view frustum, then fills and/or outlines
+    // whatever is left of it on the canvas depending on the render mode
+    pub fn draw_triangle(&mut self, a:&ClipVert, b:&ClipVert, c:&ClipVert, surface:&Surface) {
+        if self.is_culled(a, b, c) {
+            return;
+        }
+
+        let mode = self.render_mode;
+
+        if mode != RenderMode::Wireframe {
+            // clipping may turn the triangle into a convex polygon, fill it as a fan
+            let polygon = clip_triangle(a, b, c);
+            for index in 1..polygon.len().saturating_sub(1) {
+                self.fill_projected_triangle(&polygon[0], &polygon[index], &polygon[index + 1], surface);
+            }
+        }
+
+        if mode != RenderMode::Filled {
+            // keep the outline in front of the face it was filled over
+            let bias = if mode == RenderMode::FilledWireframe { 1.0 - WIREFRAME_DEPTH_BIAS } else { 1.0 };
+
+            // edges are clipped one by one so the cuts along the frustum planes are not outlined
+            for (start, end) in [(a, b), (b, c), (c, a)] {
+                if let Some((start, end)) = clip_line(start, end) {
+                    self.draw_projected_line(&start, &end, bias, surface.wireframe_color);
+                }
+            }
+        }
+    }
+
+    // model_matx places the mesh in world space, the mesh itself is left as it is, drawn unlit
+    pub fn draw_mesh(&mut self, mesh:&Mesh, model_matx:&Matrix4<f32>, camera:&Camera) {
+        let projection_matx = camera.projection_matrix(self.framebuffer.aspect_ratio());
+        self.draw_mesh_in_view(mesh, &(model_matx * camera.view_matrix()), projection_matx, &[], None);
+    }
+
+    // model_view_matx takes the mesh straight into view space, for cameras placed by something other than themselves
+    // lights are in view space too, filled triangles are shaded by them unless there are none
+    // texture is what the mesh's material refers to, it is left off if the mesh has no uvs
+    pub fn draw_mesh_in_view(&mut self, mesh:&Mesh, model_view_matx:&Matrix4<f32>, projection_matx:Matrix4<f32>, lights:&[PlacedLight], texture:Option<&Texture>) {
+        // move all verts into view space then project them
+        let view_verts:Vec<Vert3> = mesh.verts.iter().map(|vert| vert.transformed(model_view_matx)).collect();
+        let mut clip_verts:Vec<ClipVert> = view_verts.iter().map(|vert| persp_project_vert(*vert, projection_matx)).collect();
+
+        let lit = !lights.is_empty() && self.render_mode != RenderMode::Wireframe;
+        if lit && self.shading != Shading::Flat {
+            let smooth_normals;
+            let normals = if mesh.normals.len() == mesh.verts.len() {
+                &mesh.normals
+            } else {
+                smooth_normals = mesh.smooth_normals(NormalWeighting::Area);
+                &smooth_normals
+            };
+            let normal_matx = make_normal_matrix(model_view_matx);
+            for ((clip_vert, view_vert), normal) in clip_verts.iter_mut().zip(&view_verts).zip(normals) {
+                let normal = normal.transformed_normal(&normal_matx);
+                if self.shading == Shading::Gouraud {
+                    let light = diffuse_lighting(lights, view_vert.to_vector(), normal.to_vector());
+                    clip_vert.shade = [light.x, light.y, light.z];
+                }
+                clip_vert.normal = [normal.x, normal.y, normal.z];
+            }
+        }
+        let has_uvs = mesh.uvs.len() == mesh.verts.len();
+        if has_uvs {
+            for (clip_vert, uv) in clip_verts.iter_mut().zip(&mesh.uvs) {
+                clip_vert.uv = [uv.u, uv.v];
+            }
+        }
+        // an orthographic projection leaves w at 1 instead of following z
+        let surface = Surface {
+            material: &mesh.material,
+            texture: texture.filter(|_| has_uvs),
+            lights,
+            orthographic: projection_matx.m34 == 0.0,
+            wireframe_color: mesh.wireframe_color
+        };
+
+        // draw triangles between projected points
+        for tri in mesh.tris.chunks_exact(3) {
+            let (mut a, mut b, mut c) = (clip_verts[tri[0]], clip_verts[tri[1]], clip_verts[tri[2]]);
+            if lit && self.shading == Shading::Flat {
+                let (view_a, view_b, view_c) = (view_verts[tri[0]].to_vector(), view_verts[tri[1]].to_vector(), view_verts[tri[2]].to_vector());
+                let normal = (view_b - view_a).cross(&(view_c - view_a));
+                let light = diffuse_lighting(lights, (view_a + view_b + view_c) / 3.0, normal.try_normalize(0.0).unwrap_or(normal));
+                a.shade = [light.x, light.y, light.z];
+                (b.shade, c.shade) = (a.shade, a.shade);
+            }
+            self.draw_triangle(&a, &b, &c, &surface);
+        }
+    }
+
+    // clears the buffers and draws every mesh in the scene from its active camera, fogged if the scene has fog
+    pub fn render_scene_to_buffer(&mut self, scene:&Scene){
+        self.clear_frame_buffer(&scene.background);
+        let (camera, view_matx) = scene.view();
+        let projection_matx = camera.projection_matrix(self.framebuffer.aspect_ratio());
+        let lights = scene.view_lights(&view_matx);
+        for (id, world_matx) in scene.world_matrices(){
+            if let Some(mesh) = &scene.nodes[&id].mesh {
+                let texture = mesh.material.texture.and_then(|texture_id| scene.textures.get(&texture_id));
+                self.draw_mesh_in_view(mesh, &(world_matx * view_matx), projection_matx, &lights, texture);
+            }
+        }
+        if let Some(fog) = &scene.fog {
+            self.apply_fog_pass_from_z_buffer(fog);
+        }
+    }
+}
+
+#[test]
+fn rasterize_triangle_test(){
+    let (width, height) = (480, 480);
+
+    // right triangle covering the lower left half of a 10x10 pixel square, both windings
+    let a = (10.0, 10.0);
+    let b = (20.0, 20.0);
+    let c = (10.0, 20.0);
+    for (p0, p1, p2) in [(a, b, c), (a, c, b)] {
+        let mut covered = 0;
+        rasterize_triangle(p0, p1, p2, width, height, |x, y, w_a, w_b, w_c| {
+            assert!((10..20).contains(&x) && (10..20).contains(&y));
+            assert!((w_a + w_b + w_c - 1.0).abs() < 0.0001);
+            covered += 1;
+        });
+        // 45 pixels strictly below the diagonal plus 10 whose centers sit on it
+        assert_eq!(covered, 55);
+    }
+
+    // degenerate and off canvas triangles cover nothing
+    rasterize_triangle(a, b, (30.0, 30.0), width, height, |_, _, _, _, _| panic!("degenerate triangle was rasterized"));
+    rasterize_triangle((-50.0, -50.0), (-10.0, -50.0), (-10.0, -10.0), width, height, |_, _, _, _, _| panic!("off canvas triangle was rasterized"));
+
+    // huge triangle is clamped to the canvas
+    let mut covered = 0;
+    rasterize_triangle((-10000.0, -10000.0), (10000.0, -10000.0), (0.0, 10000.0), width, height, |_, _, _, _, _| covered += 1);
+    assert_eq!(covered, width * height);
+}
+
+#[test]
+fn depth_func_test(){
+    assert!( DepthFunc::Less.passes(1.0, 2.0) && !DepthFunc::Less.passes(2.0, 2.0) );
+    assert!( DepthFunc::LessEqual.passes(2.0, 2.0) && !DepthFunc::LessEqual.passes(3.0, 2.0) );
+    assert!( DepthFunc::Greater.passes(3.0, 2.0) && !DepthFunc::Greater.passes(2.0, 2.0) );
+    assert!( DepthFunc::Always.passes(f32::MAX, 0.0) );
+    assert!( !DepthFunc::Never.passes(0.0, f32::MAX) );
+}
+
+#[test]
+fn homogeneous_winding_test(){
+    let vert = |x:f32, y:f32, w:f32| ClipVert{x, y, z:0.5 * w, w, depth:w, ..Default::default()};
+
+    // clockwise on the canvas (y up) is negative
+    let (top, right, left) = (vert(0.0, 1.0, 1.0), vert(1.0, -1.0, 1.0), vert(-1.0, -1.0, 1.0));
+    assert!(homogeneous_winding(&top, &right, &left) < 0.0);
+    assert!(homogeneous_winding(&top, &left, &right) > 0.0);
+
+    // scaling a vert's homogeneous coords by a positive w doesn't change the projected winding
+    let far_right = vert(4.0, -4.0, 4.0);
+    assert!(homogeneous_winding(&top, &far_right, &left) < 0.0);
+
+    // collinear verts have no area
+    assert_eq!(homogeneous_winding(&top, &vert(0.0, 0.0, 1.0), &vert(0.0, -1.0, 1.0)), 0.0);
+}
+
+#[test]
+fn independent_renderers_test(){
+    // two renderers of different sizes draw into their own buffers
+    let mut small = Renderer::new(20, 10);
+    let mut large = Renderer::new(64, 64);
+    small.clear_frame_buffer(&Background::default());
+    large.clear_frame_buffer(&Background::default());
+
+    assert!( small.draw_line(&Line2d_i((0, 5), (19, 5)), 1.0, 1.0, Color::BLACK) );
+    assert_eq!(&small.framebuffer.color[(5 * 20 + 3) * 4..(5 * 20 + 3) * 4 + 4], &[0, 0, 0, 255]);
+    assert!( large.framebuffer.color.iter().all(|&channel| channel == 255) );
+
+    // depth test keeps the nearer line
+    small.depth_func = DepthFunc::Less;
+    assert!( small.draw_line(&Line2d_i((0, 5), (19, 5)), 2.0, 2.0, Color::BLACK) );
+    assert_eq!(small.framebuffer.depth[5 * 20 + 3], 1.0);
+}
+
+#[test]
+fn render_leaves_mesh_untouched_test(){
+    use crate::scene::transform::Transform;
+
+    let mut renderer = Renderer::new(64, 64);
+    let mut scene = Scene::new(Camera::new_default());
+    let cube = scene.add_mesh(Mesh::cube(1.0));
+
+    // draw the same spinning cube over and over, it should come out the same each time a full turn has passed
+    let mut first_frame = None;
+    for turn in 0..3 {
+        for degrees in (0..360).step_by(30) {
+            let mut transform = Transform::from_translation(0.0, 0.0, 5.0);
+            transform.set_rotation_degrees(degrees as f32, degrees as f32 * 2.0, 0.0);
+            scene.set_transform(cube, transform);
+            renderer.render_scene_to_buffer(&scene);
+            if degrees == 0 {
+                match &first_frame {
+                    None => first_frame = Some(renderer.framebuffer.color.clone()),
+                    Some(frame) => assert_eq!(frame, &renderer.framebuffer.color, "turn {} drifted", turn)
+                }
+            }
+        }
+    }
+    assert!(first_frame.unwrap().iter().any(|&channel| channel != 255));
+    assert_eq!(scene.node(cube).unwrap().mesh.as_ref().unwrap().verts, Mesh::cube(1.0).verts);
+}
+
+#[test]
+fn lit_render_test(){
+    use crate::scene::{SceneNode, transform::Transform};
+
+    let mut renderer = Renderer::new(64, 64);
+    renderer.render_mode = RenderMode::Filled;
+    let mut scene = Scene::new(Camera::new_default());
+    let sphere = scene.add_mesh(Mesh::uv_sphere(1.0, 16, 32));
+    scene.set_transform(sphere, Transform::from_translation(0.0, 0.0, 2.5));
+    let pixel = |renderer:&Renderer, x:usize, y:usize| renderer.framebuffer.color[(y * 64 + x) * 4];
+    let fill = (Material::default().base_color.0 * 255.0).round() as u8;
+
+    // no lights, plain fill color everywhere
+    renderer.render_scene_to_buffer(&scene);
+    assert_eq!(pixel(&renderer, 32, 32), fill);
+    assert_eq!(pixel(&renderer, 32, 18), fill);
+
+    // half ambient plus half from a light shining along the view direction, the middle faces it head on
+    scene.add_node(None, SceneNode::with_light(Light::ambient((1.0, 1.0, 1.0), 0.5)));
+    let mut sun = SceneNode::with_light(Light::directional((1.0, 1.0, 1.0), 0.5));
+    sun.transform.set_direction(Vert3{x:0.0, y:0.0, z:1.0});
+    let sun = scene.add_node(None, sun).unwrap();
+    for shading in [Shading::Gouraud, Shading::Flat] {
+        renderer.shading = shading;
+        renderer.render_scene_to_buffer(&scene);
+        let (middle, rim) = (pixel(&renderer, 32, 32), pixel(&renderer, 32, 18));
+        assert!(middle > 190 && middle <= fill, "{:?} {}", shading, middle);
+        assert!(rim < middle - 10 && rim >= fill / 2, "{:?} {}", shading, rim);
+    }
+
+    // flat shading gives each triangle one shade, gouraud blends them
+    let shades = |renderer:&Renderer| renderer.framebuffer.color.chunks_exact(4).map(|pixel| pixel[0]).collect::<std::collections::HashSet<_>>().len();
+    let flat_shades = shades(&renderer);
+    renderer.shading = Shading::Gouraud;
+    renderer.render_scene_to_buffer(&scene);
+    assert!(shades(&renderer) > flat_shades);
+
+    // turned to light the sphere from behind, only the ambient half is left facing the camera
+    scene.node_mut(sun).unwrap().transform.set_direction(Vert3{x:0.0, y:0.0, z:-1.0});
+    renderer.render_scene_to_buffer(&scene);
+    assert_eq!(pixel(&renderer, 32, 32), fill / 2);
+}
+
+#[test]
+fn blinn_phong_render_test(){
+    use crate::scene::{SceneNode, transform::Transform};
+
+    let mut renderer = Renderer::new(64, 64);
+    renderer.render_mode = RenderMode::Filled;
+    let mut scene = Scene::new(Camera::new_default());
+    let sphere = scene.add_mesh(Mesh::uv_sphere(1.0, 16, 32));
+    scene.set_transform(sphere, Transform::from_translation(0.0, 0.0, 2.5));
+    scene.add_node(None, SceneNode::with_light(Light::ambient((1.0, 1.0, 1.0), 0.25)));
+    let mut sun = SceneNode::with_light(Light::directional((1.0, 1.0, 1.0), 0.75));
+    sun.transform.set_direction(Vert3{x:0.0, y:0.0, z:1.0});
+    scene.add_node(None, sun);
+    let pixel = |renderer:&Renderer, x:usize, y:usize| renderer.framebuffer.color[(y * 64 + x) * 4];
+
+    // a matte surface lit per pixel looks like gouraud shading
+    renderer.shading = Shading::Gouraud;
+    renderer.render_scene_to_buffer(&scene);
+    let gouraud = renderer.framebuffer.color.clone();
+    renderer.shading = Shading::BlinnPhong;
+    renderer.render_scene_to_buffer(&scene);
+    assert!(gouraud.iter().zip(&renderer.framebuffer.color).all(|(a, b)| a.abs_diff(*b) <= 3));
+
+    // a shiny one has a highlight in the middle that falls off well before the rim
+    let material = &mut scene.node_mut(sphere).unwrap().mesh.as_mut().unwrap().material;
+    *material = Material::shiny((0.5, 0.5, 0.5), 64.0);
+    renderer.render_scene_to_buffer(&scene);
+    assert_eq!(pixel(&renderer, 32, 32), 255);
+    let near_rim = pixel(&renderer, 32, 22);
+    assert!(near_rim < 128 && near_rim > 64, "{}", near_rim);
+
+    // emissive light shows even where no light reaches
+    let material = &mut scene.node_mut(sphere).unwrap().mesh.as_mut().unwrap().material;
+    *material = Material { emissive: (0.4, 0.0, 0.0), ..Material::matte((0.0, 0.0, 0.0)) };
+    renderer.render_scene_to_buffer(&scene);
+    assert_eq!(&renderer.framebuffer.color[(32 * 64 + 32) * 4..(32 * 64 + 32) * 4 + 3], &[102, 0, 0]);
+}
+
+#[test]
+fn perspective_correct_texture_test(){
+    use crate::scene::texture::*;
+    use crate::scene::transform::Transform;
+
+    // floor stretching away from the camera, a 2x2 texture splits it into 4 squares of solid color
+    let mut renderer = Renderer::new(64, 64);
+    renderer.render_mode = RenderMode::Filled;
+    let mut scene = Scene::new(Camera::new_default());
+    let pixels = vec![255, 0, 0, 255,  0, 255, 0, 255,  0, 0, 255, 255,  255, 255, 255, 255];
+    let texture = scene.add_texture(Texture::from_rgba(2, 2, pixels).unwrap());
+    let mut floor = Mesh::plane_grid(2.0, 8.0, 1, 1);
+    floor.material = Material { texture: Some(texture), texture_filter: TextureFilter::Nearest, ..Material::matte((1.0, 1.0, 1.0)) };
+    let floor = scene.add_mesh(floor);
+    scene.set_transform(floor, Transform::from_translation(0.0, -1.0, 5.0));
+    renderer.render_scene_to_buffer(&scene);
+
+    // the texture's middle (z = 5) lands 32 / 5 pixels below the horizon, while blending uvs straight across
+    // the canvas would have put it half way between the far edge (z = 9) and the bottom of the canvas (z = 1)
+    let color = |renderer:&Renderer, y:usize| renderer.framebuffer.color[(y * 64 + 28) * 4..(y * 64 + 28) * 4 + 3].to_vec();
+    assert_eq!(color(&renderer, 37), [255, 0, 0]); // far half shows the top row
+    assert_eq!(color(&renderer, 39), [0, 0, 255]);
+    assert_eq!(color(&renderer, 45), [0, 0, 255]);
+    assert_eq!(color(&renderer, 60), [0, 0, 255]);
+
+    // a texture that isn't in the scene leaves the mesh in its base color
+    scene.remove_texture(texture);
+    renderer.render_scene_to_buffer(&scene);
+    assert_eq!(color(&renderer, 60), [255, 255, 255]);
+}
+
+#[test]
+fn trilinear_texture_test(){
+    use crate::scene::transform::Transform;
+
+    // 64x64 black and white checker on a long floor, near the horizon dozens of texels squeeze into each pixel
+    let mut renderer = Renderer::new(64, 64);
+    renderer.render_mode = RenderMode::Filled;
+    let mut scene = Scene::new(Camera::new_default());
+    let mut pixels = Vec::new();
+    for index in 0..64 * 64 {
+        let value = if (index % 64 + index / 64) % 2 == 0 { 0 } else { 255 };
+        pixels.extend_from_slice(&[value, value, value, 255]);
+    }
+    let texture = scene.add_texture(Texture::from_rgba(64, 64, pixels).unwrap());
+    let mut floor = Mesh::plane_grid(8.0, 40.0, 1, 1);
+    floor.material = Material { texture: Some(texture), ..Material::matte((1.0, 1.0, 1.0)) };
+    let floor = scene.add_mesh(floor);
+    scene.set_transform(floor, Transform::from_translation(0.0, -1.0, 21.0));
+
+    // spread of the greys along a row just below the horizon
+    let spread = |renderer:&Renderer| {
+        let row:Vec<u8> = (16..48).map(|x| renderer.framebuffer.color[(36 * 64 + x) * 4]).collect();
+        row.iter().max().unwrap() - row.iter().min().unwrap()
+    };
+    renderer.render_scene_to_buffer(&scene);
+    assert!(spread(&renderer) > 100); // bilinear picks out whichever texels land on the pixel centers
+
+    // the checker averages out to an even grey further down the mip chain
+    scene.node_mut(floor).unwrap().mesh.as_mut().unwrap().material.texture_filter = TextureFilter::Trilinear;
+    renderer.render_scene_to_buffer(&scene);
+    assert!(spread(&renderer) < 10);
+    let grey = renderer.framebuffer.color[(36 * 64 + 32) * 4];
+    assert!((120..=136).contains(&grey));
+}
+
+#[test]
+fn fog_pass_test(){
+    use crate::scene::fog::*;
+    use crate::scene::transform::Transform;
+
+    assert_eq!(mix_values(100.0, 200.0, 0.25), 125.0);
+
+    // black cube filling the canvas with its front face 20 away, half way through linear fog from 10 to 30
+    let mut renderer = Renderer::new(16, 16);
+    renderer.render_mode = RenderMode::Filled;
+    let mut scene = Scene::new(Camera::new_default());
+    let mut cube = Mesh::cube(10.0);
+    cube.material = Material::matte((0.0, 0.0, 0.0));
+    let cube = scene.add_mesh(cube);
+    scene.set_transform(cube, Transform::from_translation(0.0, 0.0, 30.0));
+    let center = |renderer:&Renderer| renderer.framebuffer.color[(8 * 16 + 8) * 4..(8 * 16 + 8) * 4 + 3].to_vec();
+
+    renderer.render_scene_to_buffer(&scene);
+    assert_eq!(center(&renderer), [0, 0, 0]);
+
+    scene.fog = Some(Fog::linear((1.0, 0.5, 0.0), 10.0, 30.0));
+    renderer.render_scene_to_buffer(&scene);
+    assert_eq!(center(&renderer), [128, 64, 0]);
+
+    // dense enough to hide the cube completely, the background isn't touched
+    scene.fog = Some(Fog::exponential_squared((0.0, 0.0, 1.0), 1.0));
+    renderer.render_scene_to_buffer(&scene);
+    assert_eq!(center(&renderer), [0, 0, 255]);
+    scene.set_transform(cube, Transform::from_translation(0.0, 0.0, 200.0));
+    renderer.render_scene_to_buffer(&scene);
+    assert_eq!(center(&renderer), [255, 255, 255]);
+}
+
+#[test]
+fn line_and_background_color_test(){
+    use crate::scene::transform::Transform;
+
+    // two wireframe cubes side by side in their own colors over a gradient
+    let mut renderer = Renderer::new(64, 64);
+    let mut scene = Scene::new(Camera::new_default());
+    scene.background = Background::VerticalGradient { top: Color::rgb(0, 0, 255), bottom: Color::rgb(0, 0, 0) };
+    let mut left = Mesh::cube(1.0);
+    left.wireframe_color = Color::rgb(255, 0, 0);
+    let left = scene.add_mesh(left);
+    scene.set_transform(left, Transform::from_translation(-2.0, 0.0, 5.0));
+    let mut right = Mesh::cube(1.0);
+    right.wireframe_color = Color::rgb(0, 255, 0);
+    let right = scene.add_mesh(right);
+    scene.set_transform(right, Transform::from_translation(2.0, 0.0, 5.0));
+    renderer.render_scene_to_buffer(&scene);
+
+    let pixel = |renderer:&Renderer, x:usize, y:usize| Color::from_array(renderer.framebuffer.color[(y * 64 + x) * 4..(y * 64 + x) * 4 + 4].try_into().unwrap());
+    let colors_in = |renderer:&Renderer, columns:std::ops::Range<usize>| {
+        let mut colors:Vec<Color> = (0..64).flat_map(|y| columns.clone().map(move |x| (x, y))).map(|(x, y)| pixel(renderer, x, y)).collect();
+        colors.retain(|color| color.r != 0 || color.g != 0);
+        colors.dedup();
+        colors
+    };
+    assert_eq!(colors_in(&renderer, 0..32), [Color::rgb(255, 0, 0)]);
+    assert_eq!(colors_in(&renderer, 32..64), [Color::rgb(0, 255, 0)]);
+    assert_eq!(pixel(&renderer, 32, 0), Color::rgb(0, 0, 255));
+    assert_eq!(pixel(&renderer, 32, 63), Color::BLACK);
+
+    // each draw call picks its own color, see through ones blend over what is already there
+    renderer.clear_frame_buffer(&Background::Solid(Color::WHITE));
+    renderer.draw_line(&Line2d_i((0, 10), (63, 10)), 1.0, 1.0, Color::rgba(255, 0, 0, 128));
+    renderer.draw_line(&Line2d_i((0, 20), (63, 20)), 1.0, 1.0, Color::rgb(0, 0, 255));
+    assert_eq!(pixel(&renderer, 30, 10), Color::rgb(255, 127, 127));
+    assert_eq!(pixel(&renderer, 30, 20), Color::rgb(0, 0, 255));
+}
+
+#[test]
+fn antialiased_line_test(){
+    let mut renderer = Renderer::new(32, 32);
+    renderer.clear_frame_buffer(&Background::default());
+    renderer.line_mode = LineMode::AntiAliased;
+    let brightness = |renderer:&Renderer, x:usize, y:usize| renderer.framebuffer.color[(y * 32 + x) * 4];
+
+    // a quarter of the way from row 10 to row 11, the nearer row gets 3 / 4 of the line
+    let point = |x:f32, y:f32, z:f32| ClipPoint2d{ x, y, z, w: 1.0 };
+    assert!( renderer.draw_segment(&point(2.0, 10.25, 1.0), &point(20.0, 10.25, 3.0), Color::BLACK) );
+    assert_eq!((brightness(&renderer, 10, 10), brightness(&renderer, 10, 11), brightness(&renderer, 10, 12)), (64, 191, 255));
+    // depth is still written part way along the line
+    assert!((renderer.framebuffer.depth[10 * 32 + 11] - 2.0).abs() < 0.0001);
+
+    // a shallow slope spreads each column across two rows that add up to the whole line
+    renderer.clear_frame_buffer(&Background::default());
+    assert!( renderer.draw_segment(&point(0.0, 5.0, 1.0), &point(30.0, 15.0, 1.0), Color::BLACK) );
+    for x in 1..30 {
+        let darkness:u32 = (0..32).map(|y| 255 - brightness(&renderer, x, y) as u32).sum();
+        assert!((253..=257).contains(&darkness));
+    }
+    // steep ones share across columns instead, and blend into what is there
+    assert!( renderer.draw_segment(&point(16.5, 0.0, 0.5), &point(16.5, 31.0, 0.5), Color::rgb(255, 0, 0)) );
+    assert_eq!(&renderer.framebuffer.color[(20 * 32 + 16) * 4..(20 * 32 + 18) * 4], &[255, 127, 127, 255, 255, 127, 127, 255]);
+
+    // on whole pixel coords the line fills its pixels like an aliased one, only the half covered ends are lighter
+    renderer.clear_frame_buffer(&Background::default());
+    assert!( renderer.draw_line(&Line2d_i((3, 4), (28, 4)), 1.0, 1.0, Color::BLACK) );
+    assert!((4..28).all(|x| brightness(&renderer, x, 4) == 0 && brightness(&renderer, x, 3) == 255 && brightness(&renderer, x, 5) == 255));
+    assert_eq!((brightness(&renderer, 3, 4), brightness(&renderer, 28, 4)), (127, 127));
+}
+
+#[test]
+fn crossing_triangles_depth_test(){
+    use crate::scene::SceneNode;
+
+    // a wall at z = 10 and a floor tilted up through it, crossing at y = 0 (the middle row of the canvas),
+    // the floor runs from z = 5.5 to z = 28 so blending its depth straight across the canvas puts the crossing far too low
+    let quad = |corners:[(f32, f32, f32); 4], color:(f32, f32, f32)| {
+        let verts = corners.iter().map(|&(x, y, z)| Vert3{x, y, z}).collect();
+        let mut mesh = Mesh::new(verts, vec![0, 1, 2, 0, 2, 3]);
+        mesh.material = Material::matte(color);
+        mesh
+    };
+    let mut scene = Scene::new(Camera::new_default());
+    scene.add_mesh(quad([(-20.0, -20.0, 10.0), (-20.0, 20.0, 10.0), (20.0, 20.0, 10.0), (20.0, -20.0, 10.0)], (1.0, 0.0, 0.0)));
+    scene.add_mesh(quad([(-20.0, -5.0, 5.5), (-20.0, 20.0, 28.0), (20.0, 20.0, 28.0), (20.0, -5.0, 5.5)], (0.0, 1.0, 0.0)));
+    let mut renderer = Renderer::new(64, 64);
+    renderer.render_mode = RenderMode::Filled;
+    renderer.cull_mode = CullMode::None;
+
+    // the same for blended corner colors and for surfaces worked out pixel by pixel
+    for per_pixel in [false, true] {
+        if per_pixel {
+            renderer.shading = Shading::BlinnPhong;
+            scene.add_node(None, SceneNode::with_light(Light::ambient((1.0, 1.0, 1.0), 1.0)));
+        }
+        renderer.render_scene_to_buffer(&scene);
+        let color = |y:usize| renderer.framebuffer.color[(y * 64 + 32) * 4..(y * 64 + 32) * 4 + 3].to_vec();
+        for y in 20..32 {
+            assert_eq!(color(y), [255, 0, 0], "row {}", y); // wall in front of the floor above the crossing
+        }
+        for y in 32..46 {
+            assert_eq!(color(y), [0, 255, 0], "row {}", y); // floor in front of the wall below it
+        }
+        // the depth buffer holds the floor's true view space depth, on row y_ndc it is 10 / (1 - 0.9 * y_ndc)
+        let y_ndc = -(38.5 - 32.0) / 32.0;
+        assert!((renderer.framebuffer.depth[38 * 64 + 32] - 10.0 / (1.0 - 0.9 * y_ndc)).abs() < 0.01);
+    }
+}
+
+#[test]
+fn greater_depth_func_test(){
+    // a small red wall in front of a bigger green one, with nothing behind the canvas corners
+    let wall = |half:f32, z:f32, color:(f32, f32, f32)| {
+        let verts = vec![Vert3{x:-half, y:-half, z}, Vert3{x:-half, y:half, z}, Vert3{x:half, y:half, z}, Vert3{x:half, y:-half, z}];
+        let mut mesh = Mesh::new(verts, vec![0, 1, 2, 0, 2, 3]);
+        mesh.material = Material::matte(color);
+        mesh
+    };
+    let mut scene = Scene::new(Camera::new_default());
+    scene.add_mesh(wall(1.0, 10.0, (1.0, 0.0, 0.0)));
+    scene.add_mesh(wall(5.0, 20.0, (0.0, 1.0, 0.0)));
+    scene.fog = Some(Fog::linear((0.0, 0.0, 0.0), 0.0, 1000.0));
+    let mut renderer = Renderer::new(64, 64);
+    renderer.render_mode = RenderMode::Filled;
+    let color = |renderer:&Renderer, x:usize, y:usize| renderer.framebuffer.color[(y * 64 + x) * 4..(y * 64 + x) * 4 + 3].to_vec();
+
+    renderer.render_scene_to_buffer(&scene);
+    assert!(color(&renderer, 32, 32)[0] > 200);
+
+    // nothing is farther than f32::MAX, so greater needs the z buffer cleared to 0 to draw anything
+    renderer.depth_func = DepthFunc::Greater;
+    renderer.render_scene_to_buffer(&scene);
+    assert_eq!(color(&renderer, 32, 32), [255, 255, 255]);
+
+    renderer.clear_depth = 0.0;
+    renderer.render_scene_to_buffer(&scene);
+    assert!(color(&renderer, 32, 32)[1] > 200 && color(&renderer, 32, 32)[0] == 0);
+    assert_eq!(renderer.framebuffer.depth[32 * 64 + 32], 20.0);
+    // the fog pass still knows which pixels were never drawn to
+    assert_eq!(color(&renderer, 0, 0), [255, 255, 255]);
+    assert_eq!(renderer.framebuffer.depth[0], 0.0);
+}
@@ -1,9 +1,298 @@
 pub mod camera;
+pub mod fog;
+pub mod light;
+pub mod material;
+pub mod texture;
 pub mod mesh;
+pub mod transform;
+
+use std::collections::BTreeMap;
+use nalgebra::Matrix4;
+
+use crate::color::Background;
+
+// handle to a node in a scene, stays valid until that node is removed and is never reused
+pub type NodeId = u32;
+
+// a node is placed by its transform relative to its parent, so moving a node moves everything below it
+pub struct SceneNode {
+    pub name:String, // as given by the file it was loaded from, empty otherwise
+    pub transform:transform::Transform, // local space to the parent's space, composed into a matrix at render time
+    pub mesh:Option<mesh::Mesh>,
+    pub camera:Option<camera::Camera>, // looks out from the node, only used when it is the scene's active camera
+    pub light:Option<light::Light>, // shines from the node's origin or down its +z axis depending on the kind
+    parent:Option<NodeId>,
+    children:Vec<NodeId>
+}
+impl SceneNode {
+    // node with nothing attached, useful to group and move other nodes together
+    pub fn empty() -> Self {
+        Self { name: String::new(), transform: transform::Transform::identity(), mesh: None, camera: None, light: None, parent: None, children: Vec::new() }
+    }
+    pub fn with_mesh(mesh:mesh::Mesh) -> Self {
+        Self { mesh: Some(mesh), ..Self::empty() }
+    }
+    pub fn with_camera(camera:camera::Camera) -> Self {
+        Self { camera: Some(camera), ..Self::empty() }
+    }
+    pub fn with_light(light:light::Light) -> Self {
+        Self { light: Some(light), ..Self::empty() }
+    }
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
 
 pub struct Scene{
-    pub meshes:Vec<mesh::Mesh>,
-    pub camera:camera::Camera
+    pub nodes:BTreeMap<NodeId, SceneNode>, // ordered by id so root nodes are drawn in the order they were added
+    pub camera:camera::Camera, // used when there is no active camera node
+    pub textures:BTreeMap<texture::TextureId, texture::Texture>, // shared by every material that refers to them
+    pub fog:Option<fog::Fog>, // laid over the drawn meshes by the renderer's fog pass, the background is left clear
+    pub background:Background, // what the canvas is cleared to before drawing
+    active_camera:Option<NodeId>,
+    next_id:NodeId,
+    next_texture_id:texture::TextureId
 }
 impl Scene{       
+    pub fn new(camera:camera::Camera) -> Self {
+        Self { nodes: BTreeMap::new(), camera, textures: BTreeMap::new(), fog: None, background: Background::default(), active_camera: None, next_id: 0, next_texture_id: 0 }
+    }
+
+    // adds the node under parent, or as a root when parent is None
+    // returns the handle used to update or remove it, None if the parent isn't in this scene
+    pub fn add_node(&mut self, parent:Option<NodeId>, mut node:SceneNode) -> Option<NodeId> {
+        let id = self.next_id;
+        if let Some(parent_id) = parent {
+            self.nodes.get_mut(&parent_id)?.children.push(id);
+        }
+        self.next_id += 1;
+        node.parent = parent;
+        node.children.clear();
+        self.nodes.insert(id, node);
+        Some(id)
+    }
+
+    // adds the mesh as a root node at the world origin
+    pub fn add_mesh(&mut self, mesh:mesh::Mesh) -> NodeId {
+        self.add_node(None, SceneNode::with_mesh(mesh)).expect("root nodes have no parent to miss")
+    }
+
+    // removes the node along with everything below it and hands the node back
+    // None if the handle doesn't belong to a node in this scene
+    pub fn remove_node(&mut self, id:NodeId) -> Option<SceneNode> {
+        let node = self.nodes.remove(&id)?;
+        if let Some(parent) = node.parent.and_then(|parent_id| self.nodes.get_mut(&parent_id)) {
+            parent.children.retain(|&child| child != id);
+        }
+        let mut stack = node.children.clone();
+        while let Some(child_id) = stack.pop() {
+            if let Some(child) = self.nodes.remove(&child_id) {
+                stack.extend(child.children);
+            }
+        }
+        if self.active_camera.is_some_and(|camera_id| !self.nodes.contains_key(&camera_id)) {
+            self.active_camera = None;
+        }
+        Some(node)
+    }
+
+    // moves the node under a new parent, or makes it a root when parent is None, keeping its local transform
+    // returns false if either handle is missing or the node would end up below itself
+    pub fn set_parent(&mut self, id:NodeId, parent:Option<NodeId>) -> bool {
+        if !self.nodes.contains_key(&id) {
+            return false;
+        }
+        if let Some(parent_id) = parent {
+            // walk up from the new parent, finding the node on the way means a cycle
+            let mut ancestor = Some(parent_id);
+            while let Some(ancestor_id) = ancestor {
+                if ancestor_id == id {
+                    return false;
+                }
+                match self.nodes.get(&ancestor_id) {
+                    Some(node) => ancestor = node.parent,
+                    None => return false
+                }
+            }
+        }
+
+        let old_parent = self.nodes[&id].parent;
+        if let Some(old) = old_parent.and_then(|parent_id| self.nodes.get_mut(&parent_id)) {
+            old.children.retain(|&child| child != id);
+        }
+        if let Some(new) = parent.and_then(|parent_id| self.nodes.get_mut(&parent_id)) {
+            new.children.push(id);
+        }
+        self.nodes.get_mut(&id).unwrap().parent = parent;
+        true
+    }
+
+    // returns the handle materials use to refer to the texture
+    pub fn add_texture(&mut self, texture:texture::Texture) -> texture::TextureId {
+        let id = self.next_texture_id;
+        self.next_texture_id += 1;
+        self.textures.insert(id, texture);
+        id
+    }
+
+    // materials still referring to the texture are drawn untextured
+    pub fn remove_texture(&mut self, id:texture::TextureId) -> Option<texture::Texture> {
+        self.textures.remove(&id)
+    }
+
+    pub fn node(&self, id:NodeId) -> Option<&SceneNode> {
+        self.nodes.get(&id)
+    }
+
+    pub fn node_mut(&mut self, id:NodeId) -> Option<&mut SceneNode> {
+        self.nodes.get_mut(&id)
+    }
+
+    // returns false if the handle doesn't belong to a node in this scene
+    pub fn set_transform(&mut self, id:NodeId, transform:transform::Transform) -> bool {
+        match self.node_mut(id) {
+            Some(node) => {
+                node.transform = transform;
+                true
+            }
+            None => false
+        }
+    }
+
+    // local space of the node to world space, found by walking up through its parents
+    pub fn world_matrix(&self, id:NodeId) -> Option<Matrix4<f32>> {
+        let mut node = self.nodes.get(&id)?;
+        let mut world_matx = node.transform.matrix();
+        while let Some(parent) = node.parent.and_then(|parent_id| self.nodes.get(&parent_id)) {
+            // row vectors, so the parent's transform goes on the right
+            world_matx *= parent.transform.matrix();
+            node = parent;
+        }
+        Some(world_matx)
+    }
+
+    // world matrix of every node, found in one pass down the tree, parents come before their children
+    pub fn world_matrices(&self) -> Vec<(NodeId, Matrix4<f32>)> {
+        let mut world_matrices = Vec::with_capacity(self.nodes.len());
+        let mut stack:Vec<(NodeId, Matrix4<f32>)> = self.nodes.iter()
+            .filter(|(_, node)| node.parent.is_none())
+            .rev()
+            .map(|(&id, _)| (id, Matrix4::identity()))
+            .collect();
+        while let Some((id, parent_matx)) = stack.pop() {
+            let node = &self.nodes[&id];
+            let world_matx = node.transform.matrix() * parent_matx;
+            stack.extend(node.children.iter().rev().map(|&child| (child, world_matx)));
+            world_matrices.push((id, world_matx));
+        }
+        world_matrices
+    }
+
+    // renders through the camera attached to this node from now on, false if it has no camera
+    pub fn set_active_camera(&mut self, id:Option<NodeId>) -> bool {
+        if let Some(camera_id) = id {
+            if self.nodes.get(&camera_id).is_none_or(|node| node.camera.is_none()) {
+                return false;
+            }
+        }
+        self.active_camera = id;
+        true
+    }
+
+    pub fn active_camera_node(&self) -> Option<NodeId> {
+        self.active_camera
+    }
+
+    // camera to render with along with its world space to view space matrix
+    pub fn view(&self) -> (&camera::Camera, Matrix4<f32>) {
+        let active = self.active_camera.and_then(|id| Some((self.nodes.get(&id)?.camera.as_ref()?, self.world_matrix(id)?)));
+        match active {
+            Some((camera, world_matx)) => {
+                // into the node's local space first, the camera then sits in that space like it would in the world
+                let to_local = world_matx.try_inverse().unwrap_or_else(Matrix4::identity);
+                (camera, to_local * camera.view_matrix())
+            }
+            None => (&self.camera, self.camera.view_matrix())
+        }
+    }
+
+    // every light in the scene placed in view space, ready to shade view space verts
+    pub fn view_lights(&self, view_matx:&Matrix4<f32>) -> Vec<light::PlacedLight> {
+        self.world_matrices().iter()
+            .filter_map(|(id, world_matx)| Some(self.nodes[id].light.as_ref()?.placed(&(world_matx * view_matx))))
+            .collect()
+    }
+}
+
+#[test]
+fn scene_handles_test() {
+    let mut scene = Scene::new(camera::Camera::new_default());
+    let cube = scene.add_mesh(mesh::Mesh::cube(1.0));
+    let sphere = scene.add_mesh(mesh::Mesh::ico_sphere(1.0, 0));
+    assert_ne!(cube, sphere);
+
+    let moved = transform::Transform::from_translation(1.0, 2.0, 3.0);
+    assert!(scene.set_transform(sphere, moved));
+    assert_eq!(scene.node(sphere).unwrap().transform, moved);
+    assert_eq!(scene.node(cube).unwrap().transform, transform::Transform::identity());
+
+    // removed handles are dead for good, even after more objects are added
+    assert_eq!(scene.remove_node(cube).unwrap().mesh.unwrap().verts.len(), 8);
+    assert!(scene.remove_node(cube).is_none());
+    assert!(!scene.set_transform(cube, moved));
+    let triangle = scene.add_mesh(mesh::Mesh::primitive_triangle(1.0));
+    assert!(triangle != cube && scene.node(cube).is_none());
+    assert_eq!(scene.nodes.len(), 2);
+}
+
+#[test]
+fn scene_graph_test() {
+    let close = |a:mesh::Vert3, b:mesh::Vert3| (a.x - b.x).abs() < 0.0001 && (a.y - b.y).abs() < 0.0001 && (a.z - b.z).abs() < 0.0001;
+    let origin = mesh::Vert3{x:0.0, y:0.0, z:0.0};
+
+    // sun at the origin spinning a quarter turn, earth 10 out along x, moon 2 further out
+    let mut scene = Scene::new(camera::Camera::new_default());
+    let sun = scene.add_mesh(mesh::Mesh::ico_sphere(3.0, 0));
+    let mut sun_transform = transform::Transform::identity();
+    sun_transform.set_rotation_degrees(0.0, 90.0, 0.0);
+    scene.set_transform(sun, sun_transform);
+    let earth = scene.add_node(Some(sun), SceneNode::with_mesh(mesh::Mesh::ico_sphere(1.0, 0))).unwrap();
+    scene.set_transform(earth, transform::Transform::from_translation(10.0, 0.0, 0.0));
+    let moon = scene.add_node(Some(earth), SceneNode::with_mesh(mesh::Mesh::ico_sphere(0.5, 0))).unwrap();
+    scene.set_transform(moon, transform::Transform::from_translation(2.0, 0.0, 0.0));
+    assert_eq!(scene.node(earth).unwrap().children(), &[moon]);
+
+    // turning the sun carries the earth and the moon with it
+    let sun_turned = mesh::Vert3{x:10.0, y:0.0, z:0.0}.transformed(&sun_transform.matrix());
+    assert!(close(origin.transformed(&scene.world_matrix(earth).unwrap()), sun_turned));
+    let moon_world = origin.transformed(&scene.world_matrix(moon).unwrap());
+    assert!(close(moon_world, mesh::Vert3{x:sun_turned.x * 1.2, y:sun_turned.y * 1.2, z:sun_turned.z * 1.2}));
+
+    // traversal matches walking up from each node and visits parents first
+    let world_matrices = scene.world_matrices();
+    assert_eq!(world_matrices.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![sun, earth, moon]);
+    for (id, world_matx) in &world_matrices {
+        assert!((world_matx - scene.world_matrix(*id).unwrap()).abs().max() < 0.0001);
+    }
+
+    // no cycles, moving the moon to the root leaves it at its local position
+    assert!(!scene.set_parent(sun, Some(moon)));
+    assert!(!scene.set_parent(earth, Some(earth)));
+    assert!(scene.set_parent(moon, None));
+    assert!(scene.node(earth).unwrap().children().is_empty());
+    assert!(close(origin.transformed(&scene.world_matrix(moon).unwrap()), mesh::Vert3{x:2.0, y:0.0, z:0.0}));
+
+    // a camera riding on the earth looks out from the earth's world position
+    let eye = scene.add_node(Some(earth), SceneNode::with_camera(camera::Camera::new_default())).unwrap();
+    assert!(!scene.set_active_camera(Some(moon)));
+    assert!(scene.set_active_camera(Some(eye)));
+    assert!(close(sun_turned.transformed(&scene.view().1), origin));
+
+    // removing a node takes its whole subtree, including the active camera
+    scene.remove_node(sun);
+    assert_eq!(scene.nodes.keys().copied().collect::<Vec<_>>(), vec![moon]);
+    assert_eq!(scene.active_camera_node(), None);
 }
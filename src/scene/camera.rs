@@ -1,22 +1,15 @@
 extern crate nalgebra;
-use nalgebra::{Matrix4};
+use nalgebra::{Matrix4, UnitQuaternion, Vector3};
 
-// Define the size of our canvas
-// in the future will be loaded from config, min size 10x10
-const CANVAS_WIDTH:usize = 480;
-const CANVAS_HEIGHT:usize = 480;
+use super::mesh::Vert3;
+use crate::transformations::make_translation_matrix;
 
-const AR:f32 = CANVAS_WIDTH as f32 / CANVAS_HEIGHT as f32; // aspect ratio of window (height over width)
-
-const CANVAS_SLOPE:f32     = CANVAS_HEIGHT as f32 / CANVAS_WIDTH as f32;
-const INV_CANVAS_SLOPE:f32 = 1.0 / CANVAS_SLOPE;
-
-pub fn make_perspective_matrix(fov_angle_degrees:f32, znear:f32, zfar:f32) -> Matrix4<f32>{
+// fov is the vertical field of view, aspect ratio is the canvas width over height
+pub fn make_perspective_matrix(fov_angle_degrees:f32, aspect_ratio:f32, znear:f32, zfar:f32) -> Matrix4<f32>{
     let t = fov_angle_degrees.to_radians();
     let scale = 1.0 / (t/2.0).tan();
-    // this is only an ortho proj right now
     let persp_proj_matrix:Matrix4<f32> = Matrix4::new(
-        AR * scale   , 0.0  ,             0.0             , 0.0,
+    scale / aspect_ratio, 0.0  ,             0.0             , 0.0,
             0.0      , scale,             0.0             , 0.0,
             0.0      , 0.0  ,     zfar / (zfar-znear)     , 1.0,
             0.0      , 0.0  , -((zfar*znear)/(zfar-znear)), 0.0
@@ -24,27 +17,170 @@ pub fn make_perspective_matrix(fov_angle_degrees:f32, znear:f32, zfar:f32) -> Ma
     persp_proj_matrix
 }
 
+// maps the box left..right, bottom..top, znear..zfar in view space onto clip space, w is always 1
+pub fn make_orthographic_matrix(left:f32, right:f32, bottom:f32, top:f32, znear:f32, zfar:f32) -> Matrix4<f32>{
+    let ortho_proj_matrix:Matrix4<f32> = Matrix4::new(
+                2.0 / (right - left)        ,                0.0                ,          0.0          , 0.0,
+                        0.0                 ,        2.0 / (top - bottom)       ,          0.0          , 0.0,
+                        0.0                 ,                0.0                , 1.0 / (zfar - znear)  , 0.0,
+        -(right + left) / (right - left)    , -(top + bottom) / (top - bottom)  , -znear / (zfar - znear), 1.0
+    );
+    ortho_proj_matrix
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Projection {
+    Perspective { fov_angle_degrees:f32 },
+    // no foreshortening, view volume centered on the view direction, height units tall and as wide as the canvas aspect ratio asks
+    Orthographic { height:f32 },
+    // no foreshortening, explicit bounds of the view volume in view space units, ignores the canvas aspect ratio
+    OrthographicBounds { left:f32, right:f32, bottom:f32, top:f32 }
+}
+impl Projection {
+    pub fn matrix(&self, aspect_ratio:f32, znear:f32, zfar:f32) -> Matrix4<f32> {
+        match *self {
+            Projection::Perspective { fov_angle_degrees } => make_perspective_matrix(fov_angle_degrees, aspect_ratio, znear, zfar),
+            Projection::Orthographic { height } => {
+                let half_height = height / 2.0;
+                let half_width = half_height * aspect_ratio;
+                make_orthographic_matrix(-half_width, half_width, -half_height, half_height, znear, zfar)
+            }
+            Projection::OrthographicBounds { left, right, bottom, top } => make_orthographic_matrix(left, right, bottom, top, znear, zfar)
+        }
+    }
+}
+
+// view space is left handed: x right, y up, looking down +z
 pub struct Camera {
-    pub fov_angle_degrees:f32, 
+    pub projection:Projection,
     pub znear:f32, 
     pub zfar:f32,
-    pub pers_tranfm_matx:Matrix4<f32>
+    pub position:Vert3, // world space
+    pub orientation:UnitQuaternion<f32> // rotates view space axes into world space
 }
 impl Camera {
     pub fn new_default() -> Self {
-        Self {
-            fov_angle_degrees: 90.0, 
-            znear: 0.1, 
-            zfar: 100.0,
-            pers_tranfm_matx: { make_perspective_matrix(90.0, 0.1, 100.0) }
-        }
+        Self::new(90.0, 0.1, 100.0)
     }
     pub fn new(fov_degrees:f32, z_near:f32, z_far:f32) -> Self {
+        Self::with_projection(Projection::Perspective { fov_angle_degrees: fov_degrees }, z_near, z_far)
+    }
+    // orthographic camera showing a view volume height units tall
+    pub fn new_orthographic(height:f32, z_near:f32, z_far:f32) -> Self {
+        Self::with_projection(Projection::Orthographic { height }, z_near, z_far)
+    }
+    pub fn with_projection(projection:Projection, z_near:f32, z_far:f32) -> Self {
         Self {
-            fov_angle_degrees: fov_degrees, 
+            projection,
             znear: z_near, 
             zfar: z_far,
-            pers_tranfm_matx: { make_perspective_matrix(fov_degrees, z_near, z_far) }
+            position: Vert3{x:0.0, y:0.0, z:0.0},
+            orientation: UnitQuaternion::identity()
+        }
+    }
+    // default projection placed at eye and looking at target
+    pub fn look_at(eye:Vert3, target:Vert3, up:Vert3) -> Self {
+        let mut camera = Self::new_default();
+        camera.set_look_at(eye, target, up);
+        camera
+    }
+
+    // moves the camera to eye and turns it towards target, keeping "up" as close to up as possible
+    // orientation is left alone if target is on top of eye or straight along up
+    pub fn set_look_at(&mut self, eye:Vert3, target:Vert3, up:Vert3) {
+        self.position = eye;
+
+        let dir = Vector3::new(target.x - eye.x, target.y - eye.y, target.z - eye.z);
+        let up = Vector3::new(up.x, up.y, up.z);
+        if dir.cross(&up).norm_squared() > f32::EPSILON {
+            // maps +z onto dir with +x = up x dir, which is screen right for this left handed view
+            self.orientation = UnitQuaternion::face_towards(&dir, &up);
         }
     }
-}
\ No newline at end of file
+
+    // view space to clip space for a canvas with the given width over height
+    pub fn projection_matrix(&self, aspect_ratio:f32) -> Matrix4<f32> {
+        self.projection.matrix(aspect_ratio, self.znear, self.zfar)
+    }
+
+    // yaw turns right around y, pitch tilts up around x, roll tilts clockwise around z, applied in that order
+    pub fn set_rotation_degrees(&mut self, yaw:f32, pitch:f32, roll:f32) {
+        let yaw_rot   = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw.to_radians());
+        let pitch_rot = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -pitch.to_radians());
+        let roll_rot  = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), -roll.to_radians());
+        self.orientation = yaw_rot * pitch_rot * roll_rot;
+    }
+
+    // world space to view space, applied to row vectors like the other transforms (vert * matx)
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        // moving the world by -position then rotating by the inverse orientation, which for a
+        // row vector is the orientation's column vector matrix as is
+        make_translation_matrix(-self.position.x, -self.position.y, -self.position.z) * self.orientation.to_homogeneous()
+    }
+}
+
+#[test]
+fn view_matrix_test() {
+    let close = |a:Vert3, b:Vert3| (a.x - b.x).abs() < 0.0001 && (a.y - b.y).abs() < 0.0001 && (a.z - b.z).abs() < 0.0001;
+    let up = Vert3{x:0.0, y:1.0, z:0.0};
+
+    // default camera leaves world space as it is
+    let origin_cam = Camera::new_default();
+    let pt = Vert3{x:1.0, y:2.0, z:3.0};
+    assert!(close(pt.transformed(&origin_cam.view_matrix()), pt));
+
+    // camera looking along +x from the origin sees a point on the +x axis straight ahead
+    // and a point on +z to its left
+    let side_cam = Camera::look_at(Vert3{x:0.0, y:0.0, z:0.0}, Vert3{x:10.0, y:0.0, z:0.0}, up);
+    assert!(close(Vert3{x:5.0, y:0.0, z:0.0}.transformed(&side_cam.view_matrix()), Vert3{x:0.0, y:0.0, z:5.0}));
+    assert!(close(Vert3{x:0.0, y:0.0, z:5.0}.transformed(&side_cam.view_matrix()), Vert3{x:-5.0, y:0.0, z:0.0}));
+
+    // target always ends up straight ahead at its distance from the eye
+    let eye = Vert3{x:3.0, y:-4.0, z:12.0};
+    let target = Vert3{x:-1.0, y:2.0, z:0.0};
+    let orbit_cam = Camera::look_at(eye, target, up);
+    assert!(close(target.transformed(&orbit_cam.view_matrix()), Vert3{x:0.0, y:0.0, z:14.0}));
+
+    // yaw of 90 matches looking along +x
+    let mut yaw_cam = Camera::new_default();
+    yaw_cam.set_rotation_degrees(90.0, 0.0, 0.0);
+    assert!(close(Vert3{x:5.0, y:0.0, z:0.0}.transformed(&yaw_cam.view_matrix()), Vert3{x:0.0, y:0.0, z:5.0}));
+
+    // pitching up brings a point above the camera in front of it
+    let mut pitch_cam = Camera::new_default();
+    pitch_cam.set_rotation_degrees(0.0, 90.0, 0.0);
+    assert!(close(Vert3{x:0.0, y:5.0, z:0.0}.transformed(&pitch_cam.view_matrix()), Vert3{x:0.0, y:0.0, z:5.0}));
+}
+
+#[test]
+fn orthographic_matrix_test() {
+    let aspect_ratio = 2.0;
+    let camera = Camera::new_orthographic(20.0, 1.0, 11.0);
+    let matx = camera.projection_matrix(aspect_ratio);
+    let project = |x:f32, y:f32, z:f32| (
+        x * matx.m11 + y * matx.m21 + z * matx.m31 + matx.m41,
+        y * matx.m22 + z * matx.m32 + matx.m42,
+        z * matx.m33 + matx.m43,
+        x * matx.m14 + y * matx.m24 + z * matx.m34 + matx.m44
+    );
+
+    // top right corner of the view volume lands on the top right of clip space at any depth
+    for z in [1.0, 6.0, 11.0] {
+        let (x, y, _, w) = project(10.0 * aspect_ratio, 10.0, z);
+        assert!((x - 1.0).abs() < 0.0001 && (y - 1.0).abs() < 0.0001 && w == 1.0);
+    }
+
+    // znear and zfar map onto the 0..1 clip space depth range
+    assert!(project(0.0, 0.0, 1.0).2.abs() < 0.0001);
+    assert!((project(0.0, 0.0, 11.0).2 - 1.0).abs() < 0.0001);
+}
+
+#[test]
+fn perspective_aspect_test() {
+    // on a canvas twice as wide as it is tall the horizontal edge is twice as far out as the vertical one
+    let matx = Camera::new(90.0, 1.0, 100.0).projection_matrix(2.0);
+    let z = 10.0;
+    let right_edge = (20.0 * matx.m11 + z * matx.m31 + matx.m41) / (z * matx.m34);
+    let top_edge = (10.0 * matx.m22 + z * matx.m32 + matx.m42) / (z * matx.m34);
+    assert!((right_edge - 1.0).abs() < 0.0001 && (top_edge - 1.0).abs() < 0.0001);
+}
@@ -0,0 +1,72 @@
+use wasm_bindgen::prelude::*;
+
+// how the fog thickens with distance from the camera
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum FogMode {
+    Linear, // none at start, rising evenly to full fog at end
+    Exponential, // 1 - e^-(density * d), quick to start and slow to finish
+    ExponentialSquared // 1 - e^-((density * d)^2), stays clear for longer then closes in fast
+}
+
+// blends drawn pixels towards a color the further away they are, distances are view space depth
+// rather than the straight line distance so fog doesn't change as the camera turns
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Fog {
+    pub mode:FogMode,
+    pub color:(f32, f32, f32), // 0..1 for each channel
+    pub start:f32, // nothing nearer than this is fogged, the exponential modes measure d from here
+    pub end:f32, // only used by linear fog, everything past it is hidden
+    pub density:f32 // only used by the exponential modes
+}
+impl Fog {
+    pub fn linear(color:(f32, f32, f32), start:f32, end:f32) -> Self {
+        Self { mode: FogMode::Linear, color, start, end, density: 0.0 }
+    }
+    pub fn exponential(color:(f32, f32, f32), density:f32) -> Self {
+        Self { mode: FogMode::Exponential, color, start: 0.0, end: 0.0, density }
+    }
+    pub fn exponential_squared(color:(f32, f32, f32), density:f32) -> Self {
+        Self { mode: FogMode::ExponentialSquared, color, start: 0.0, end: 0.0, density }
+    }
+
+    // how much of the fog color covers a pixel at depth, 0 for none up to 1 where the fog hides it completely
+    pub fn factor(&self, depth:f32) -> f32 {
+        let distance = (depth - self.start).max(0.0);
+        let factor = match self.mode {
+            // end at or before start fogs everything past start
+            FogMode::Linear if self.end <= self.start => if depth > self.start { 1.0 } else { 0.0 },
+            FogMode::Linear => distance / (self.end - self.start),
+            FogMode::Exponential => 1.0 - (-self.density * distance).exp(),
+            FogMode::ExponentialSquared => 1.0 - (-(self.density * distance).powi(2)).exp()
+        };
+        factor.clamp(0.0, 1.0)
+    }
+}
+
+#[test]
+fn fog_factor_test() {
+    let close = |a:f32, b:f32| (a - b).abs() < 0.0001;
+    let white = (1.0, 1.0, 1.0);
+
+    let linear = Fog::linear(white, 10.0, 30.0);
+    assert!(close(linear.factor(5.0), 0.0));
+    assert!(close(linear.factor(20.0), 0.5));
+    assert!(close(linear.factor(35.0), 1.0));
+
+    // e^-1 of the scene left showing at distance 1 / density, squared holds off longer but catches up there
+    let exponential = Fog::exponential(white, 0.1);
+    let squared = Fog::exponential_squared(white, 0.1);
+    assert!(close(exponential.factor(0.0), 0.0));
+    assert!(close(exponential.factor(10.0), 1.0 - (-1.0f32).exp()));
+    assert!(close(squared.factor(10.0), exponential.factor(10.0)));
+    assert!(squared.factor(5.0) < exponential.factor(5.0));
+    assert!(squared.factor(20.0) > exponential.factor(20.0));
+
+    // start pushes the exponential modes back too
+    let pushed = Fog { start: 10.0, ..exponential };
+    assert!(close(pushed.factor(10.0), 0.0));
+    assert!(close(pushed.factor(20.0), exponential.factor(10.0)));
+
+    assert!(close(Fog::linear(white, 10.0, 10.0).factor(10.5), 1.0));
+}
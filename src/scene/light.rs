@@ -0,0 +1,153 @@
+extern crate nalgebra;
+use nalgebra::{Matrix4, Vector3};
+
+use super::mesh::Vert3;
+
+// what a light does, it is placed and aimed by the node it is attached to
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum LightKind {
+    Ambient, // reaches every face evenly whichever way it faces
+    Directional, // parallel rays shining down the node's +z axis, like sunlight, where the node is doesn't matter
+    // shines out in every direction from the node's origin, fading to 1 / (constant + linear * d + quadratic * d^2) at distance d
+    Point { constant:f32, linear:f32, quadratic:f32 }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Light {
+    pub kind:LightKind,
+    pub color:(f32, f32, f32), // 0..1 for each channel
+    pub intensity:f32
+}
+impl Light {
+    pub fn ambient(color:(f32, f32, f32), intensity:f32) -> Self {
+        Self { kind: LightKind::Ambient, color, intensity }
+    }
+    pub fn directional(color:(f32, f32, f32), intensity:f32) -> Self {
+        Self { kind: LightKind::Directional, color, intensity }
+    }
+    // full strength up close, halved at distance 1 / linear when quadratic is 0
+    pub fn point(color:(f32, f32, f32), intensity:f32, linear:f32, quadratic:f32) -> Self {
+        Self { kind: LightKind::Point { constant: 1.0, linear, quadratic }, color, intensity }
+    }
+
+    // the light as seen from the space matx takes the node into, usually view space so it can shade view space verts
+    pub fn placed(&self, matx:&Matrix4<f32>) -> PlacedLight {
+        let origin = Vert3{x:0.0, y:0.0, z:0.0}.transformed(matx);
+        let forward = Vert3{x:0.0, y:0.0, z:1.0}.transformed(matx);
+        PlacedLight {
+            kind: self.kind,
+            color: Vector3::new(self.color.0, self.color.1, self.color.2) * self.intensity,
+            position: origin.to_vector(),
+            // flipped around so it points back at the light like the direction to a point light does
+            to_light: (origin.to_vector() - forward.to_vector()).try_normalize(0.0).unwrap_or_else(Vector3::zeros)
+        }
+    }
+}
+
+// a light moved into the space shading happens in
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct PlacedLight {
+    pub kind:LightKind,
+    pub color:Vector3<f32>, // color times intensity
+    pub position:Vector3<f32>, // only used by point lights
+    pub to_light:Vector3<f32> // unit length, only used by directional lights
+}
+impl PlacedLight {
+    // unit length direction towards the light from position along with the light arriving from there,
+    // None for ambient light which comes from everywhere
+    pub fn incoming(&self, position:Vector3<f32>) -> Option<(Vector3<f32>, Vector3<f32>)> {
+        match self.kind {
+            LightKind::Ambient => None,
+            LightKind::Directional => Some((self.to_light, self.color)),
+            LightKind::Point { constant, linear, quadratic } => {
+                let offset = self.position - position;
+                let distance = offset.norm();
+                if distance == 0.0 {
+                    return None;
+                }
+                let attenuation = 1.0 / (constant + linear * distance + quadratic * distance * distance).max(f32::EPSILON);
+                Some((offset / distance, self.color * attenuation))
+            }
+        }
+    }
+
+    // lambertian diffuse light per channel reaching a surface at position facing along the unit length normal
+    pub fn diffuse(&self, position:Vector3<f32>, normal:Vector3<f32>) -> Vector3<f32> {
+        match self.incoming(position) {
+            Some((to_light, color)) => color * normal.dot(&to_light).max(0.0),
+            None if self.kind == LightKind::Ambient => self.color,
+            None => Vector3::zeros()
+        }
+    }
+}
+
+// sum of the diffuse light from every light, a zero normal only picks up ambient light
+pub fn diffuse_lighting(lights:&[PlacedLight], position:Vector3<f32>, normal:Vector3<f32>) -> Vector3<f32> {
+    lights.iter().map(|light| light.diffuse(position, normal)).sum()
+}
+
+// diffuse and specular light reaching a surface at position facing along the unit length normal, seen from
+// along the unit length to_eye, highlights are blinn-phong, peaking where the normal is halfway between light and eye
+pub fn blinn_phong_lighting(lights:&[PlacedLight], position:Vector3<f32>, normal:Vector3<f32>, to_eye:Vector3<f32>, shininess:f32) -> (Vector3<f32>, Vector3<f32>) {
+    let mut diffuse = Vector3::zeros();
+    let mut specular = Vector3::zeros();
+    for light in lights {
+        diffuse += light.diffuse(position, normal);
+        if let Some((to_light, color)) = light.incoming(position) {
+            // no highlights on faces turned away from the light
+            if normal.dot(&to_light) > 0.0 {
+                if let Some(halfway) = (to_light + to_eye).try_normalize(0.0) {
+                    specular += color * normal.dot(&halfway).max(0.0).powf(shininess);
+                }
+            }
+        }
+    }
+    (diffuse, specular)
+}
+
+#[test]
+fn diffuse_lighting_test() {
+    use crate::transformations::*;
+    let close = |a:Vector3<f32>, b:Vector3<f32>| (a - b).abs().max() < 0.0001;
+    let up = Vector3::new(0.0, 1.0, 0.0);
+
+    // a sun turned to shine straight down lights an upward face fully and a sideways one not at all
+    let sun = Light::directional((1.0, 0.5, 0.25), 2.0).placed(&make_x_rotation_matrix(-90.0));
+    assert!(close(sun.to_light, up));
+    assert!(close(sun.diffuse(Vector3::zeros(), up), Vector3::new(2.0, 1.0, 0.5)));
+    assert!(close(sun.diffuse(Vector3::zeros(), Vector3::new(1.0, 0.0, 0.0)), Vector3::zeros()));
+    assert!(close(sun.diffuse(Vector3::zeros(), -up), Vector3::zeros()));
+
+    // a point light 2 above the surface, tilting the face 60 degrees away halves it
+    let bulb = Light::point((1.0, 1.0, 1.0), 1.0, 0.5, 0.0).placed(&make_translation_matrix(0.0, 2.0, 0.0));
+    assert!(close(bulb.diffuse(Vector3::zeros(), up), Vector3::new(0.5, 0.5, 0.5)));
+    let tilted = Vector3::new(60.0f32.to_radians().sin(), 60.0f32.to_radians().cos(), 0.0);
+    assert!(close(bulb.diffuse(Vector3::zeros(), tilted), Vector3::new(0.25, 0.25, 0.25)));
+
+    // ambient light doesn't care about the normal, lights add up
+    let lights = [sun, bulb, Light::ambient((0.1, 0.2, 0.3), 1.0).placed(&Matrix4::identity())];
+    assert!(close(diffuse_lighting(&lights, Vector3::zeros(), Vector3::zeros()), Vector3::new(0.1, 0.2, 0.3)));
+    assert!(close(diffuse_lighting(&lights, Vector3::zeros(), up), Vector3::new(2.6, 1.7, 1.3)));
+}
+
+#[test]
+fn blinn_phong_lighting_test() {
+    let up = Vector3::new(0.0, 1.0, 0.0);
+    let sun = Light::directional((1.0, 1.0, 1.0), 1.0).placed(&crate::transformations::make_x_rotation_matrix(-45.0));
+    let lights = [sun, Light::ambient((0.2, 0.2, 0.2), 1.0).placed(&Matrix4::identity())];
+
+    // looking straight down the reflected ray gets the full highlight, the diffuse part doesn't care where the eye is
+    let mirrored = Vector3::new(0.0, 1.0, 1.0).normalize();
+    let (diffuse, specular) = blinn_phong_lighting(&lights, Vector3::zeros(), up, mirrored, 32.0);
+    assert!((diffuse.x - (0.2 + 45.0f32.to_radians().cos())).abs() < 0.0001);
+    assert!((specular.x - 1.0).abs() < 0.0001);
+
+    // moving the eye away from the reflection shrinks the highlight, more so when shinier
+    let (_, dull) = blinn_phong_lighting(&lights, Vector3::zeros(), up, up, 8.0);
+    let (_, sharp) = blinn_phong_lighting(&lights, Vector3::zeros(), up, up, 64.0);
+    assert!(dull.x < 1.0 && sharp.x < dull.x && sharp.x > 0.0);
+
+    // nothing from behind
+    let (diffuse, specular) = blinn_phong_lighting(&lights, Vector3::zeros(), -up, -mirrored, 32.0);
+    assert!((diffuse.x - 0.2).abs() < 0.0001 && specular == Vector3::zeros());
+}
@@ -0,0 +1,28 @@
+use super::texture::{TextureFilter, TextureId};
+
+// how a mesh's surface responds to light, colors are 0..1 for each channel
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Material {
+    pub base_color:(f32, f32, f32), // diffuse color, also what the surface is drawn in when the scene has no lights
+    pub texture:Option<TextureId>, // scene texture multiplied into the base color by the mesh's uvs
+    pub texture_filter:TextureFilter, // how the texture is read, trilinear keeps far away and slanted surfaces from shimmering
+    pub specular_color:(f32, f32, f32), // color of highlights, black for a matte surface
+    pub shininess:f32, // blinn-phong exponent, higher makes smaller and sharper highlights
+    pub emissive:(f32, f32, f32) // light given off by the surface itself, added whatever lights there are
+}
+impl Material {
+    // matte surface in the given color
+    pub fn matte(base_color:(f32, f32, f32)) -> Self {
+        Self { base_color, texture: None, texture_filter: TextureFilter::Bilinear, specular_color: (0.0, 0.0, 0.0), shininess: 1.0, emissive: (0.0, 0.0, 0.0) }
+    }
+    // white highlights on top of the base color
+    pub fn shiny(base_color:(f32, f32, f32), shininess:f32) -> Self {
+        Self { specular_color: (1.0, 1.0, 1.0), shininess, ..Self::matte(base_color) }
+    }
+}
+impl Default for Material {
+    // light grey so the black wireframe overlay stays visible
+    fn default() -> Self {
+        Self::matte((200.0 / 255.0, 200.0 / 255.0, 200.0 / 255.0))
+    }
+}
@@ -1,7 +1,11 @@
 extern crate nalgebra as na;
-use na::{Matrix4};
+use na::{Matrix3, Matrix4, Vector3};
+use crate::color::Color;
+use crate::transformations::make_normal_matrix;
+use super::material::Material;
+use std::collections::HashMap;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vert3 {
     pub x: f32,
     pub y: f32,
@@ -17,14 +21,56 @@ impl Vert3{
         }
         None
     }
+    // applies the transform as a row vector (vert * transfm), translation lives in the bottom row
+    pub fn transformed(&self, transfm:&Matrix4<f32>) -> Self{
+        Self{
+            x: self.x * transfm.m11 + self.y * transfm.m21 + self.z * transfm.m31 + transfm.m41,
+            y: self.x * transfm.m12 + self.y * transfm.m22 + self.z * transfm.m32 + transfm.m42,
+            z: self.x * transfm.m13 + self.y * transfm.m23 + self.z * transfm.m33 + transfm.m43
+        }
+    }
+    // applies a matrix from make_normal_matrix as a row vector and brings the result back to unit length
+    pub fn transformed_normal(&self, normal_matx:&Matrix3<f32>) -> Self{
+        let transformed = (self.to_vector().transpose() * normal_matx).transpose();
+        Self::from_vector(transformed.try_normalize(0.0).unwrap_or(transformed))
+    }
+    pub fn to_vector(self) -> Vector3<f32> {
+        Vector3::new(self.x, self.y, self.z)
+    }
+    pub fn from_vector(vector:Vector3<f32>) -> Self {
+        Self{x:vector.x, y:vector.y, z:vector.z}
+    }
+}
+
+// how much each face around a vert counts towards the vert's smooth normal
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NormalWeighting {
+    Area, // big faces count more, cheap and fine for evenly tessellated meshes
+    Angle // faces count by the angle of their corner at the vert, doesn't depend on how the faces are split up
+}
+
+// texture coordinates, u to the right and v up across the image, 0..1 covers the image once
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TexCoord {
+    pub u: f32,
+    pub v: f32
 }
 
 pub struct Mesh {
     pub verts: Vec<Vert3>,
-    pub tris: Vec<usize> // groups of 3, indeces into "points" vector
+    pub tris: Vec<usize>, // groups of 3, indeces into "points" vector
+    // per vert attributes, each one is either empty or as long as verts
+    pub normals: Vec<Vert3>,
+    pub uvs: Vec<TexCoord>,
+    pub material: Material,
+    pub wireframe_color: Color // outlines drawn by the wireframe render modes
 }
 // constructors for common mesh shapes and mesh operations
 impl Mesh {
+    // mesh with only positions and triangles, no normals or uvs, in the default material
+    pub fn new(verts:Vec<Vert3>, tris:Vec<usize>) -> Self {
+        Self{ verts, tris, normals: Vec::new(), uvs: Vec::new(), material: Material::default(), wireframe_color: Color::BLACK }
+    }
     fn add_verts(&mut self, new_verts:&mut Vec<Vert3>){
         self.verts.append(new_verts);
     }
@@ -54,8 +100,9 @@ impl Mesh {
             z:0.0
         };
         
-        Self{ verts:vec![vert_one, vert_two, vert_three], tris:vec![0,1,2] } // drawing the triangle clockwise    
+        Self::new(vec![vert_one, vert_two, vert_three], vec![0,1,2]) // drawing the triangle clockwise    
     }
+    // level 0 is the icosahedron, every level splits each triangle into 4 so there are 20 * 4^level of them
     pub fn ico_sphere(size:f32, level:i32) -> Self{
         // adapted from https://schneide.blog/2016/07/15/generating-an-icosphere-in-c/
         let a:f32 = 0.525731112119133606 * size;
@@ -68,14 +115,45 @@ impl Mesh {
             Vert3{x: b, y:a, z:c}, Vert3{x:-b, y:a, z: c}, Vert3{x: b, y:-a, z: c}, Vert3{x:-b, y:-a, z: c}
         ];
         
+        // wound clockwise on screen when seen from outside the sphere, same as the cube
         let tri_list = vec![
-            0, 4, 1,  0,9, 4,  9, 5,4,   4,5,8,  4,8, 1,
-            8,10, 1,  8,3,10,  5, 3,8,   5,2,3,  2,7, 3,
-            7,10, 3,  7,6,10,  7,11,6,  11,0,6,  0,1, 6,
-            6, 1,10,  9,0,11,  9,11,2,   9,2,5,  7,2,11
+            0, 1, 4,  0, 4,9,  9,4, 5,   4,8,5,  4, 1,8,
+            8, 1,10,  8,10,3,  5,8, 3,   5,3,2,  2, 3,7,
+            7, 3,10,  7,10,6,  7,6,11,  11,6,0,  0, 6,1,
+            6,10, 1,  9,11,0,  9,2,11,   9,5,2,  7,11,2
         ];
         
-        Self{verts:vert_list, tris:tri_list}
+        let mut sphere = Self::new(vert_list, tri_list);
+        for _ in 0..level.max(0) {
+            sphere.subdivide_sphere(size);
+        }
+        sphere
+    }
+    // splits every triangle into 4 at its edge midpoints, pushed out to radius so the mesh stays a sphere
+    // each midpoint is made once and shared by the two triangles on either side of its edge
+    fn subdivide_sphere(&mut self, radius:f32) {
+        let mut midpoint_cache:HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint = |verts:&mut Vec<Vert3>, start:usize, end:usize| -> usize {
+            let edge = (start.min(end), start.max(end));
+            *midpoint_cache.entry(edge).or_insert_with(|| {
+                let (a, b) = (verts[start], verts[end]);
+                let mid = Vert3{x:(a.x + b.x) * 0.5, y:(a.y + b.y) * 0.5, z:(a.z + b.z) * 0.5};
+                let scale = radius / (mid.x * mid.x + mid.y * mid.y + mid.z * mid.z).sqrt();
+                verts.push(Vert3{x:mid.x * scale, y:mid.y * scale, z:mid.z * scale});
+                verts.len() - 1
+            })
+        };
+
+        let mut new_tris = Vec::with_capacity(self.tris.len() * 4);
+        for tri in self.tris.chunks_exact(3) {
+            let (a, b, c) = (tri[0], tri[1], tri[2]);
+            let ab = midpoint(&mut self.verts, a, b);
+            let bc = midpoint(&mut self.verts, b, c);
+            let ca = midpoint(&mut self.verts, c, a);
+            // corners first then the middle, all keeping the winding of the original triangle
+            new_tris.extend_from_slice(&[a, ab, ca,  b, bc, ab,  c, ca, bc,  ab, bc, ca]);
+        }
+        self.tris = new_tris;
     }
     pub fn cube(size:f32) -> Self {
         let vert_list = vec![
@@ -104,16 +182,391 @@ impl Mesh {
             4, 0, 1
         ];
 
-        Self{verts:vert_list, tris:tri_list}
+        Self::new(vert_list, tri_list)
+    }
+    // y up sphere made of rings of latitude and segments of longitude, with a single vert at each pole
+    pub fn uv_sphere(radius:f32, rings:usize, segments:usize) -> Self {
+        let rings = rings.max(2);
+        let profile:Vec<(f32, f32)> = (0..=rings)
+            .map(|ring| {
+                let angle = std::f32::consts::PI * ring as f32 / rings as f32;
+                (radius * angle.sin(), radius * angle.cos())
+            })
+            .collect();
+        Self::lathe(&profile, segments, false)
+    }
+    // y up cylinder centered on the origin, caps close off the top and bottom
+    pub fn cylinder(radius:f32, height:f32, segments:usize, caps:bool) -> Self {
+        let half = height / 2.0;
+        if caps {
+            Self::lathe(&[(0.0, half), (radius, half), (radius, -half), (0.0, -half)], segments, false)
+        } else {
+            Self::lathe(&[(radius, half), (radius, -half)], segments, false)
+        }
+    }
+    // y up cone centered on the origin with its tip at the top, the cap closes off the base
+    pub fn cone(radius:f32, height:f32, segments:usize, cap:bool) -> Self {
+        let half = height / 2.0;
+        if cap {
+            Self::lathe(&[(0.0, half), (radius, -half), (0.0, -half)], segments, false)
+        } else {
+            Self::lathe(&[(0.0, half), (radius, -half)], segments, false)
+        }
+    }
+    // ring around the y axis, major_radius out to the middle of the tube which is minor_radius thick
+    pub fn torus(major_radius:f32, minor_radius:f32, major_segments:usize, minor_segments:usize) -> Self {
+        let minor_segments = minor_segments.max(3);
+        // going down the outside of the tube first keeps the faces pointing out
+        let profile:Vec<(f32, f32)> = (0..minor_segments)
+            .map(|segment| {
+                let angle = -std::f32::consts::TAU * segment as f32 / minor_segments as f32;
+                (major_radius + minor_radius * angle.cos(), minor_radius * angle.sin())
+            })
+            .collect();
+        Self::lathe(&profile, major_segments, true)
+    }
+    // flat grid on the xz plane centered on the origin and facing up +y, split into x_segments by z_segments quads
+    // uvs cover a texture once, u along +x and v along +z
+    pub fn plane_grid(width:f32, depth:f32, x_segments:usize, z_segments:usize) -> Self {
+        let (x_segments, z_segments) = (x_segments.max(1), z_segments.max(1));
+        let mut verts = Vec::with_capacity((x_segments + 1) * (z_segments + 1));
+        let mut uvs = Vec::with_capacity(verts.capacity());
+        for x in 0..=x_segments {
+            for z in 0..=z_segments {
+                let (u, v) = (x as f32 / x_segments as f32, z as f32 / z_segments as f32);
+                verts.push(Vert3{x: width * (u - 0.5), y: 0.0, z: depth * (v - 0.5)});
+                uvs.push(TexCoord{u, v});
+            }
+        }
+        let index = |x:usize, z:usize| x * (z_segments + 1) + z;
+        let mut tris = Vec::with_capacity(x_segments * z_segments * 6);
+        for x in 0..x_segments {
+            for z in 0..z_segments {
+                let (a, b, c, d) = (index(x, z), index(x, z + 1), index(x + 1, z + 1), index(x + 1, z));
+                tris.extend_from_slice(&[a, b, c,  a, c, d]);
+            }
+        }
+        Self { uvs, ..Self::new(verts, tris) }
+    }
+    // y up capsule, a cylinder height tall between two half spheres, rings is the number of rings in each half sphere
+    pub fn capsule(radius:f32, height:f32, rings:usize, segments:usize) -> Self {
+        let rings = rings.max(1);
+        let half = height / 2.0;
+        let quarter_turn = std::f32::consts::FRAC_PI_2;
+        let mut profile = Vec::with_capacity(rings * 2 + 2);
+        for ring in 0..=rings {
+            let angle = quarter_turn * ring as f32 / rings as f32;
+            profile.push((radius * angle.sin(), half + radius * angle.cos()));
+        }
+        for ring in 0..=rings {
+            let angle = quarter_turn * (1.0 + ring as f32 / rings as f32);
+            profile.push((radius * angle.sin(), -half + radius * angle.cos()));
+        }
+        Self::lathe(&profile, segments, false)
+    }
+    // spins the (distance from the y axis, y) profile around the y axis, profile points on the axis become a single vert
+    // the profile runs top to bottom along the outside so faces point away from the axis,
+    // a closed profile also joins its last point back to the first
+    fn lathe(profile:&[(f32, f32)], segments:usize, closed:bool) -> Self {
+        let segments = segments.max(3);
+        let mut verts = Vec::new();
+        // index of the first vert of each profile point, along with whether it is a single vert on the axis
+        let mut rings:Vec<(usize, bool)> = Vec::with_capacity(profile.len());
+        // sin(PI) and the like don't come out as exactly 0, so anything this close counts as on the axis
+        let axis_tolerance = profile.iter().fold(0.0f32, |widest, point| widest.max(point.0.abs())) * 0.00001;
+        for &(distance, y) in profile {
+            let on_axis = distance.abs() <= axis_tolerance;
+            rings.push((verts.len(), on_axis));
+            if on_axis {
+                verts.push(Vert3{x:0.0, y, z:0.0});
+                continue;
+            }
+            for segment in 0..segments {
+                let angle = std::f32::consts::TAU * segment as f32 / segments as f32;
+                verts.push(Vert3{x:distance * angle.cos(), y, z:distance * angle.sin()});
+            }
+        }
+
+        let vert_at = |(start, on_axis):(usize, bool), segment:usize| if on_axis { start } else { start + segment % segments };
+        let mut tris = Vec::new();
+        let ring_pairs = if closed { rings.len() } else { rings.len().saturating_sub(1) };
+        for ring in 0..ring_pairs {
+            let (upper, lower) = (rings[ring], rings[(ring + 1) % rings.len()]);
+            for segment in 0..segments {
+                let (a, b) = (vert_at(upper, segment), vert_at(upper, segment + 1));
+                let (c, d) = (vert_at(lower, segment + 1), vert_at(lower, segment));
+                // a quad turns into one triangle where either ring is a single vert on the axis
+                if !upper.1 {
+                    tris.extend_from_slice(&[a, b, c]);
+                }
+                if !lower.1 {
+                    tris.extend_from_slice(&[a, c, d]);
+                }
+            }
+        }
+        Self::new(verts, tris)
     }
     pub fn transform(&mut self, transfm:Matrix4<f32>){
-        for vert_index in 0..self.verts.len(){
-            let mut temp_vert:Vert3  = self.verts[vert_index];
-            self.verts[vert_index].x = temp_vert.x * transfm.m11 + temp_vert.y * transfm.m21 + temp_vert.z * transfm.m31 + transfm.m41;
-            self.verts[vert_index].y = temp_vert.x * transfm.m12 + temp_vert.y * transfm.m22 + temp_vert.z * transfm.m32 + transfm.m42;
-            self.verts[vert_index].z = temp_vert.x * transfm.m13 + temp_vert.y * transfm.m23 + temp_vert.z * transfm.m33 + transfm.m43;
-            //let w:f32                = temp_vert.x * transfm.m14 + temp_vert.y * transfm.m24 + temp_vert.z * transfm.m34 + transfm.m44;
+        for vert in self.verts.iter_mut(){
+            *vert = vert.transformed(&transfm);
+        }
+        let normal_matx = make_normal_matrix(&transfm);
+        for normal in self.normals.iter_mut() {
+            *normal = normal.transformed_normal(&normal_matx);
+        }
+    }
+    // mirrors z and swaps two corners of every triangle so faces keep facing the same way,
+    // takes a mesh from a right handed file format into our left handed space and back again
+    pub fn flip_handedness(&mut self) {
+        for vert in self.verts.iter_mut().chain(self.normals.iter_mut()) {
+            vert.z = -vert.z;
+        }
+        for tri in self.tris.chunks_exact_mut(3) {
+            tri.swap(1, 2);
+        }
+    }
+
+    // unit length right handed normal (counter clockwise seen from the front) of every triangle,
+    // zero for triangles with no area
+    pub fn face_normals(&self) -> Vec<Vert3> {
+        self.tris.chunks_exact(3).map(|tri| {
+            let (a, b, c) = (self.verts[tri[0]].to_vector(), self.verts[tri[1]].to_vector(), self.verts[tri[2]].to_vector());
+            let normal = (b - a).cross(&(c - a));
+            Vert3::from_vector(normal.try_normalize(0.0).unwrap_or(normal))
+        }).collect()
+    }
+
+    // smooth vertex normals without touching the mesh, what compute_normals gives without a crease angle
+    pub fn smooth_normals(&self, weighting:NormalWeighting) -> Vec<Vert3> {
+        let face_normals:Vec<Vector3<f32>> = self.face_normals().iter().map(|normal| normal.to_vector()).collect();
+        let mut sums = vec![Vector3::zeros(); self.verts.len()];
+        for (&vert, weight) in self.tris.iter().zip(self.corner_weights(&face_normals, weighting)) {
+            sums[vert] += weight;
+        }
+        sums.into_iter().map(|sum| Vert3::from_vector(sum.try_normalize(0.0).unwrap_or(sum))).collect()
+    }
+
+    // each face's contribution to the normal at each of its 3 corners, in the same order as tris
+    fn corner_weights(&self, face_normals:&[Vector3<f32>], weighting:NormalWeighting) -> Vec<Vector3<f32>> {
+        let mut corner_weights = Vec::with_capacity(self.tris.len());
+        for (face, tri) in self.tris.chunks_exact(3).enumerate() {
+            let corners = [self.verts[tri[0]].to_vector(), self.verts[tri[1]].to_vector(), self.verts[tri[2]].to_vector()];
+            for corner in 0..3 {
+                let weight = match weighting {
+                    // the cross product's length is twice the triangle's area
+                    NormalWeighting::Area => (corners[1] - corners[0]).cross(&(corners[2] - corners[0])).norm(),
+                    NormalWeighting::Angle => {
+                        let (to_next, to_prev) = (corners[(corner + 1) % 3] - corners[corner], corners[(corner + 2) % 3] - corners[corner]);
+                        if to_next.norm() > 0.0 && to_prev.norm() > 0.0 { to_next.angle(&to_prev) } else { 0.0 }
+                    }
+                };
+                corner_weights.push(face_normals[face] * weight);
+            }
+        }
+        corner_weights
+    }
+
+    // fills normals with smooth vertex normals averaged from the faces around each vert
+    // with a crease angle (degrees) a vert is split where the faces around it bend more sharply than that,
+    // so each side of a hard edge gets its own normal, the uvs of split verts are copied across
+    pub fn compute_normals(&mut self, weighting:NormalWeighting, crease_degrees:Option<f32>) {
+        let face_normals:Vec<Vector3<f32>> = self.face_normals().iter().map(|normal| normal.to_vector()).collect();
+        let corner_weights = self.corner_weights(&face_normals, weighting);
+        let mut vert_faces:Vec<Vec<usize>> = vec![Vec::new(); self.verts.len()];
+        for (corner, &vert) in self.tris.iter().enumerate() {
+            vert_faces[vert].push(corner / 3);
+        }
+        let min_cos = crease_degrees.map(|degrees| degrees.to_radians().cos());
+
+        let mut normals = vec![Vert3{x:0.0, y:0.0, z:0.0}; self.verts.len()];
+        let mut filled = vec![false; self.verts.len()];
+        // the split copies of each vert, looked up by the bits of their normal
+        let mut splits:HashMap<(usize, [u32; 3]), usize> = HashMap::new();
+        for corner_index in 0..self.tris.len() {
+            let (vert, face) = (self.tris[corner_index], corner_index / 3);
+            // faces are always summed in the same order, so corners that take in the same faces get exactly the same normal
+            let mut sum = Vector3::zeros();
+            for &other in &vert_faces[vert] {
+                let smooth = match min_cos {
+                    Some(min_cos) => face_normals[face] == Vector3::zeros() || face_normals[face].dot(&face_normals[other]) >= min_cos,
+                    None => true
+                };
+                if smooth {
+                    let corner = (other * 3..other * 3 + 3).find(|&index| self.tris[index] == vert).unwrap_or(other * 3);
+                    sum += corner_weights[corner];
+                }
+            }
+            let normal = Vert3::from_vector(sum.try_normalize(0.0).unwrap_or(sum));
+            let key = (vert, [normal.x.to_bits(), normal.y.to_bits(), normal.z.to_bits()]);
+            if !filled[vert] {
+                filled[vert] = true;
+                normals[vert] = normal;
+                splits.insert(key, vert);
+                continue;
+            }
+            let split = *splits.entry(key).or_insert_with(|| {
+                self.verts.push(self.verts[vert]);
+                if !self.uvs.is_empty() {
+                    self.uvs.push(self.uvs[vert]);
+                }
+                normals.push(normal);
+                self.verts.len() - 1
+            });
+            self.tris[corner_index] = split;
+        }
+        self.normals = normals;
+    }
+}
+
+// every face normal (right handed, counter clockwise) of the closed built in meshes should point
+// away from the center, which is what shows up clockwise on the canvas when looking down +z
+#[test]
+fn closed_mesh_winding_test() {
+    for mesh in [Mesh::cube(10.0), Mesh::ico_sphere(10.0, 0), Mesh::ico_sphere(10.0, 2)] {
+        for tri in mesh.tris.chunks_exact(3) {
+            let (a, b, c) = (mesh.verts[tri[0]], mesh.verts[tri[1]], mesh.verts[tri[2]]);
+            let (ab, ac) = ((b.x - a.x, b.y - a.y, b.z - a.z), (c.x - a.x, c.y - a.y, c.z - a.z));
+            let normal = (ab.1 * ac.2 - ab.2 * ac.1, ab.2 * ac.0 - ab.0 * ac.2, ab.0 * ac.1 - ab.1 * ac.0);
+            let centroid = (a.x + b.x + c.x, a.y + b.y + c.y, a.z + b.z + c.z);
+            assert!(normal.0 * centroid.0 + normal.1 * centroid.1 + normal.2 * centroid.2 > 0.0);
+        }
+    }
+}
+
+#[test]
+fn ico_sphere_levels_test() {
+    for level in 0..4 {
+        let sphere = Mesh::ico_sphere(5.0, level);
+        let faces = 20 * 4usize.pow(level as u32);
+        assert_eq!(sphere.tris.len(), faces * 3);
+        // closed mesh made of triangles, V - E + F = 2 with E = 3F / 2
+        assert_eq!(sphere.verts.len(), faces / 2 + 2);
+        for vert in &sphere.verts {
+            assert!(((vert.x * vert.x + vert.y * vert.y + vert.z * vert.z).sqrt() - 5.0).abs() < 0.0001);
+        }
+        // no two verts in the same place
+        for (index, vert) in sphere.verts.iter().enumerate() {
+            assert!(sphere.verts[index + 1..].iter().all(|other| (other.x - vert.x).abs() + (other.y - vert.y).abs() + (other.z - vert.z).abs() > 0.0001));
+        }
+    }
+    assert_eq!(Mesh::ico_sphere(1.0, -1).tris.len(), 60);
+}
+
+// every edge of a closed mesh should be shared by exactly two triangles running along it in opposite directions,
+// and with the faces pointing out the signed volume comes out positive
+#[test]
+fn closed_primitives_test() {
+    let volume = |mesh:&Mesh| mesh.tris.chunks_exact(3).map(|tri| {
+        let (a, b, c) = (mesh.verts[tri[0]], mesh.verts[tri[1]], mesh.verts[tri[2]]);
+        (a.x * (b.y * c.z - b.z * c.y) - a.y * (b.x * c.z - b.z * c.x) + a.z * (b.x * c.y - b.y * c.x)) / 6.0
+    }).sum::<f32>();
+    let pi = std::f32::consts::PI;
 
+    let primitives = [
+        (Mesh::uv_sphere(2.0, 32, 64), 4.0 / 3.0 * pi * 8.0),
+        (Mesh::cylinder(1.0, 3.0, 64, true), pi * 3.0),
+        (Mesh::cone(1.0, 3.0, 64, true), pi),
+        (Mesh::torus(3.0, 1.0, 64, 32), 2.0 * pi * pi * 3.0),
+        (Mesh::capsule(1.0, 2.0, 16, 64), pi * 2.0 + 4.0 / 3.0 * pi),
+        (Mesh::ico_sphere(2.0, 3), 4.0 / 3.0 * pi * 8.0),
+        (Mesh::cube(1.0), 8.0)
+    ];
+    for (index, (mesh, expected_volume)) in primitives.iter().enumerate() {
+        let mut edges = HashMap::new();
+        for tri in mesh.tris.chunks_exact(3) {
+            assert!(tri[0] != tri[1] && tri[1] != tri[2] && tri[2] != tri[0]);
+            for edge in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+                *edges.entry(edge).or_insert(0) += 1;
+            }
+        }
+        for (&(start, end), &count) in &edges {
+            assert_eq!(count, 1);
+            assert_eq!(edges.get(&(end, start)), Some(&1), "primitive {}", index);
+        }
+        // tessellated surfaces fall a little short of the exact volume
+        let mesh_volume = volume(mesh);
+        assert!(mesh_volume > expected_volume * 0.97 && mesh_volume <= expected_volume * 1.0001, "{} vs {}", mesh_volume, expected_volume);
+    }
+}
+
+#[test]
+fn open_primitives_test() {
+    let face_normal = |mesh:&Mesh, tri:&[usize]| {
+        let (a, b, c) = (mesh.verts[tri[0]], mesh.verts[tri[1]], mesh.verts[tri[2]]);
+        let (ab, ac) = ((b.x - a.x, b.y - a.y, b.z - a.z), (c.x - a.x, c.y - a.y, c.z - a.z));
+        (Vert3{x:ab.1 * ac.2 - ab.2 * ac.1, y:ab.2 * ac.0 - ab.0 * ac.2, z:ab.0 * ac.1 - ab.1 * ac.0}, a)
+    };
+
+    let grid = Mesh::plane_grid(4.0, 2.0, 4, 3);
+    assert_eq!((grid.verts.len(), grid.tris.len()), (20, 4 * 3 * 6));
+    assert!(grid.tris.chunks_exact(3).all(|tri| face_normal(&grid, tri).0.y > 0.0));
+    assert_eq!((grid.uvs[0], grid.uvs[19]), (TexCoord{u:0.0, v:0.0}, TexCoord{u:1.0, v:1.0}));
+
+    // open sides still face away from the axis
+    for mesh in [Mesh::cylinder(1.0, 2.0, 12, false), Mesh::cone(1.0, 2.0, 12, false)] {
+        for tri in mesh.tris.chunks_exact(3) {
+            let (normal, a) = face_normal(&mesh, tri);
+            assert!(normal.x * a.x + normal.z * a.z > 0.0 || a.x == 0.0 && a.z == 0.0);
         }
     }
+    assert_eq!(Mesh::cylinder(1.0, 2.0, 12, false).tris.len(), 12 * 6);
+    assert_eq!(Mesh::cone(1.0, 2.0, 12, false).tris.len(), 12 * 3);
+}
+
+#[test]
+fn face_and_vertex_normals_test() {
+    let close = |a:Vert3, b:Vert3| (a.x - b.x).abs() + (a.y - b.y).abs() + (a.z - b.z).abs() < 0.0001;
+
+    // the cube's faces point straight out along an axis
+    let mut cube = Mesh::cube(1.0);
+    for (normal, tri) in cube.face_normals().iter().zip(cube.tris.chunks_exact(3)) {
+        let a = cube.verts[tri[0]];
+        assert!((normal.x.abs() + normal.y.abs() + normal.z.abs() - 1.0).abs() < 0.0001);
+        assert!(normal.x * a.x + normal.y * a.y + normal.z * a.z > 0.0);
+    }
+
+    // with angle weighting every corner of the cube gets the diagonal, area weighting leans towards
+    // faces that happen to have 2 triangles meeting at the corner
+    cube.compute_normals(NormalWeighting::Angle, None);
+    assert_eq!(cube.verts.len(), 8);
+    for (vert, normal) in cube.verts.iter().zip(&cube.normals) {
+        let third = 1.0 / 3.0f32.sqrt();
+        assert!(close(*normal, Vert3{x:vert.x * third, y:vert.y * third, z:vert.z * third}));
+    }
+
+    // a crease angle under 90 degrees splits each corner into one vert per side
+    cube.compute_normals(NormalWeighting::Area, Some(45.0));
+    assert_eq!((cube.verts.len(), cube.normals.len()), (24, 24));
+    for (normal, tri) in cube.face_normals().iter().zip(cube.tris.chunks_exact(3)) {
+        assert!(tri.iter().all(|&vert| close(cube.normals[vert], *normal)));
+    }
+
+    // a sphere is smooth everywhere so nothing is split, and the normals point away from the center
+    let mut sphere = Mesh::uv_sphere(2.0, 8, 16);
+    let vert_count = sphere.verts.len();
+    sphere.compute_normals(NormalWeighting::Area, Some(45.0));
+    assert_eq!(sphere.verts.len(), vert_count);
+    for (vert, normal) in sphere.verts.iter().zip(&sphere.normals) {
+        assert!(normal.x * vert.x + normal.y * vert.y + normal.z * vert.z > 1.9);
+    }
+}
+
+#[test]
+fn transform_normals_test() {
+    // a slope squashed flat along y, the normal should tip towards y rather than follow the verts
+    let mut slope = Mesh::new(vec![Vert3{x:0.0, y:0.0, z:0.0}, Vert3{x:0.0, y:1.0, z:1.0}, Vert3{x:1.0, y:0.0, z:0.0}], vec![0, 1, 2]);
+    slope.compute_normals(NormalWeighting::Area, None);
+    slope.transform(Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 0.5, 1.0)) * Matrix4::new_translation(&Vector3::new(3.0, 4.0, 5.0)).transpose());
+    let expected = slope.face_normals()[0];
+    for normal in &slope.normals {
+        assert!((normal.x - expected.x).abs() + (normal.y - expected.y).abs() + (normal.z - expected.z).abs() < 0.0001);
+    }
+
+    // mirroring flips the winding but the normals should still point the same way as the face
+    let mut cube = Mesh::cube(1.0);
+    cube.compute_normals(NormalWeighting::Angle, None);
+    cube.transform(Matrix4::new_nonuniform_scaling(&Vector3::new(-2.0, 1.0, 1.0)));
+    for (vert, normal) in cube.verts.iter().zip(&cube.normals) {
+        assert!(normal.x * vert.x + normal.y * vert.y + normal.z * vert.z > 0.0);
+    }
 }
@@ -0,0 +1,258 @@
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+use super::mesh::TexCoord;
+
+// handle to a texture in a scene, stays valid until that texture is removed and is never reused
+pub type TextureId = u32;
+
+// how a texture is read between the centers of its texels
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TextureFilter {
+    Nearest, // the texel the point lands in, blocky up close
+    Bilinear, // blend of the 4 nearest texel centers, shimmers when many texels squeeze into a pixel
+    Trilinear // bilinear in the two mip levels closest to the pixel's size on the texture, blended between them
+}
+
+// what happens to uvs outside of 0..1
+#[wasm_bindgen]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TextureWrap {
+    Repeat, // tiles the image
+    Clamp, // stretches the edge texels outwards
+    Mirror // tiles the image, flipping every other copy so the edges line up
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureError {
+    pub message:String
+}
+impl fmt::Display for TextureError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "texture: {}", self.message)
+    }
+}
+impl std::error::Error for TextureError {}
+
+// one image in a texture's mip chain, rgba rows top to bottom
+pub struct MipLevel {
+    pub width:usize,
+    pub height:usize,
+    pub pixels:Vec<u8>
+}
+impl MipLevel {
+    // half the size (rounded down, at least 1), each texel the average of the 2x2 block it covers,
+    // the last row or column is repeated into the block when the size is odd
+    fn halved(&self) -> Self {
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let rows = [(y * 2).min(self.height - 1), (y * 2 + 1).min(self.height - 1)];
+                let columns = [(x * 2).min(self.width - 1), (x * 2 + 1).min(self.width - 1)];
+                for channel in 0..4 {
+                    let sum:u32 = rows.iter()
+                        .flat_map(|row| columns.iter().map(move |column| (row * self.width + column) * 4 + channel))
+                        .map(|index| self.pixels[index] as u32)
+                        .sum();
+                    pixels.push(((sum + 2) / 4) as u8);
+                }
+            }
+        }
+        Self { width, height, pixels }
+    }
+}
+
+// rgba image sampled across triangles by their uvs, along with its mip chain and how it wraps
+pub struct Texture {
+    pub width:usize,
+    pub height:usize,
+    pub pixels:Vec<u8>, // 4 u8 values for each texel, rgba, rows top to bottom like the framebuffer
+    pub wrap:TextureWrap,
+    mips:Vec<MipLevel> // halved over and over from pixels down to 1x1, made once when the texture is
+}
+impl Texture {
+    // pixels holds width * height rgba texels, rows top to bottom, repeated to start with
+    pub fn from_rgba(width:usize, height:usize, pixels:Vec<u8>) -> Result<Self, TextureError> {
+        if width == 0 || height == 0 {
+            return Err(TextureError { message: format!("{}x{} image has no texels", width, height) });
+        }
+        let byte_count = match width.checked_mul(height).and_then(|texels| texels.checked_mul(4)) {
+            Some(byte_count) => byte_count,
+            None => return Err(TextureError { message: format!("{}x{} image is too big", width, height) })
+        };
+        if pixels.len() != byte_count {
+            return Err(TextureError { message: format!("{}x{} rgba image needs {} bytes, found {}", width, height, byte_count, pixels.len()) });
+        }
+        let mut levels = vec![MipLevel { width, height, pixels }];
+        while let Some(last) = levels.last().filter(|last| last.width > 1 || last.height > 1) {
+            let halved = last.halved();
+            levels.push(halved);
+        }
+        let full_size = levels.remove(0);
+        Ok(Self { width, height, pixels: full_size.pixels, wrap: TextureWrap::Repeat, mips: levels })
+    }
+
+    // number of images in the mip chain, counting the full size one
+    pub fn level_count(&self) -> usize {
+        self.mips.len() + 1
+    }
+
+    // size and texels of mip level index, 0 is the full size image
+    fn level(&self, index:usize) -> (usize, usize, &[u8]) {
+        match index {
+            0 => (self.width, self.height, &self.pixels),
+            _ => {
+                let mip = &self.mips[index - 1];
+                (mip.width, mip.height, &mip.pixels)
+            }
+        }
+    }
+
+    // mip level of detail for a pixel whose uv changes by uv_dx one pixel right and uv_dy one pixel down,
+    // log2 of how many texels the pixel stretches across, 0 or less when texels are as big as pixels or bigger
+    pub fn level_of_detail(&self, uv_dx:[f32; 2], uv_dy:[f32; 2]) -> f32 {
+        let texels = |uv_d:[f32; 2]| (uv_d[0] * self.width as f32).hypot(uv_d[1] * self.height as f32);
+        texels(uv_dx).max(texels(uv_dy)).log2()
+    }
+
+    // rgba color at uv, 0..1 for each channel, lod is from level_of_detail and only used by trilinear filtering
+    pub fn sample(&self, uv:TexCoord, filter:TextureFilter, lod:f32) -> [f32; 4] {
+        match filter {
+            TextureFilter::Nearest => {
+                let (x, y) = self.texel_coords(0, uv);
+                self.texel(0, x.floor() as i64, y.floor() as i64)
+            }
+            TextureFilter::Bilinear => self.bilinear(0, uv),
+            TextureFilter::Trilinear => {
+                // nan lod from a zero sized pixel footprint falls back to the full size image
+                let lod = if lod.is_nan() { 0.0 } else { lod.clamp(0.0, self.mips.len() as f32) };
+                let finer = lod.floor();
+                let fraction = lod - finer;
+                let finer = finer as usize;
+                let near = self.bilinear(finer, uv);
+                // also keeps the smallest level from blending with one past the end of the chain
+                if fraction == 0.0 {
+                    return near;
+                }
+                let far = self.bilinear(finer + 1, uv);
+                [0, 1, 2, 3].map(|channel| near[channel] + (far[channel] - near[channel]) * fraction)
+            }
+        }
+    }
+
+    // uv scaled to texel coords of a mip level, v runs up the image while rows run down it
+    fn texel_coords(&self, level:usize, uv:TexCoord) -> (f32, f32) {
+        let (width, height, _) = self.level(level);
+        (uv.u * width as f32, (1.0 - uv.v) * height as f32)
+    }
+
+    // blend of the 4 texel centers around uv in a mip level
+    fn bilinear(&self, level:usize, uv:TexCoord) -> [f32; 4] {
+        // texel centers sit half way across each texel
+        let (x, y) = self.texel_coords(level, uv);
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (left, top) = (x.floor(), y.floor());
+        let (across, down) = (x - left, y - top);
+        let (left, top) = (left as i64, top as i64);
+        let (top_left, top_right) = (self.texel(level, left, top), self.texel(level, left + 1, top));
+        let (bottom_left, bottom_right) = (self.texel(level, left, top + 1), self.texel(level, left + 1, top + 1));
+        [0, 1, 2, 3].map(|channel| {
+            let top = top_left[channel] + (top_right[channel] - top_left[channel]) * across;
+            let bottom = bottom_left[channel] + (bottom_right[channel] - bottom_left[channel]) * across;
+            top + (bottom - top) * down
+        })
+    }
+
+    // texel of a mip level at whole texel coords, wrapped onto the image
+    fn texel(&self, level:usize, x:i64, y:i64) -> [f32; 4] {
+        let (width, height, pixels) = self.level(level);
+        let (x, y) = (self.wrap_coord(x, width), self.wrap_coord(y, height));
+        let start = (y * width + x) * 4;
+        [0, 1, 2, 3].map(|channel| pixels[start + channel] as f32 / 255.0)
+    }
+
+    fn wrap_coord(&self, coord:i64, size:usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self.wrap {
+            TextureWrap::Repeat => coord.rem_euclid(size),
+            TextureWrap::Clamp => coord.clamp(0, size - 1),
+            TextureWrap::Mirror => {
+                let flipped = coord.rem_euclid(size * 2);
+                if flipped < size { flipped } else { size * 2 - 1 - flipped }
+            }
+        };
+        wrapped as usize
+    }
+}
+
+#[test]
+fn texture_sample_test() {
+    // 2x2: red green on top, blue white below
+    let pixels = vec![255, 0, 0, 255,  0, 255, 0, 255,  0, 0, 255, 255,  255, 255, 255, 255];
+    let mut texture = Texture::from_rgba(2, 2, pixels).unwrap();
+    let mut filter = TextureFilter::Bilinear;
+    let uv = |u:f32, v:f32| TexCoord{u, v};
+    let close = |a:[f32; 4], b:[f32; 4]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 0.0001);
+
+    // texel centers come back exactly with either filter, v = 1 is the top row
+    for filter in [TextureFilter::Nearest, TextureFilter::Bilinear] {
+        assert!(close(texture.sample(uv(0.25, 0.75), filter, 0.0), [1.0, 0.0, 0.0, 1.0]));
+        assert!(close(texture.sample(uv(0.75, 0.25), filter, 0.0), [1.0, 1.0, 1.0, 1.0]));
+    }
+
+    // half way between the two top texels
+    assert!(close(texture.sample(uv(0.5, 0.75), filter, 0.0), [0.5, 0.5, 0.0, 1.0]));
+    filter = TextureFilter::Nearest;
+    assert!(close(texture.sample(uv(0.49, 0.75), filter, 0.0), [1.0, 0.0, 0.0, 1.0]));
+
+    // one texel past the right edge
+    let past_right = uv(1.25, 0.75);
+    assert!(close(texture.sample(past_right, filter, 0.0), [1.0, 0.0, 0.0, 1.0]));
+    texture.wrap = TextureWrap::Clamp;
+    assert!(close(texture.sample(past_right, filter, 0.0), [0.0, 1.0, 0.0, 1.0]));
+    assert!(close(texture.sample(uv(-3.0, 0.75), filter, 0.0), [1.0, 0.0, 0.0, 1.0]));
+    texture.wrap = TextureWrap::Mirror;
+    assert!(close(texture.sample(past_right, filter, 0.0), [0.0, 1.0, 0.0, 1.0]));
+    assert!(close(texture.sample(uv(1.75, 0.75), filter, 0.0), [1.0, 0.0, 0.0, 1.0]));
+    assert!(close(texture.sample(uv(-0.25, 0.75), filter, 0.0), [1.0, 0.0, 0.0, 1.0]));
+
+    // bilinear blends across the wrapped edge too
+    filter = TextureFilter::Bilinear;
+    texture.wrap = TextureWrap::Repeat;
+    assert!(close(texture.sample(uv(0.0, 0.75), filter, 0.0), [0.5, 0.5, 0.0, 1.0]));
+
+    assert!(Texture::from_rgba(2, 2, vec![0; 15]).err().unwrap().message.contains("needs 16 bytes"));
+    assert!(Texture::from_rgba(0, 2, Vec::new()).is_err());
+    assert_eq!(Texture::from_rgba(usize::MAX / 2, 3, Vec::new()).err().unwrap().message, format!("{}x3 image is too big", usize::MAX / 2));
+}
+
+#[test]
+fn mip_chain_test() {
+    // 4x2 checker of black and white texels, each column the opposite of its neighbours
+    let mut pixels = Vec::new();
+    for index in 0..8 {
+        let value = if (index % 4 + index / 4) % 2 == 0 { 0 } else { 255 };
+        pixels.extend_from_slice(&[value, value, value, 255]);
+    }
+    let texture = Texture::from_rgba(4, 2, pixels).unwrap();
+    assert_eq!(texture.level_count(), 3); // 4x2, 2x1, 1x1
+    assert_eq!(texture.level(1), (2, 1, &[128, 128, 128, 255, 128, 128, 128, 255][..]));
+    assert_eq!(texture.level(2), (1, 1, &[128, 128, 128, 255][..]));
+
+    // a pixel spanning one texel stays on the full image, one spanning 2 or 4 texels moves down the chain
+    assert_eq!(texture.level_of_detail([0.25, 0.0], [0.0, 0.5]), 0.0);
+    assert_eq!(texture.level_of_detail([0.5, 0.0], [0.0, 0.25]), 1.0);
+    assert_eq!(texture.level_of_detail([0.0, 0.0], [0.0, 2.0]), 2.0);
+
+    // trilinear smooths the checker out once the texels are smaller than the pixels, bilinear keeps the full contrast
+    let center = TexCoord{u:0.125, v:0.75};
+    assert_eq!(texture.sample(center, TextureFilter::Trilinear, -1.0), [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(texture.sample(center, TextureFilter::Bilinear, 2.0), [0.0, 0.0, 0.0, 1.0]);
+    let grey = texture.sample(center, TextureFilter::Trilinear, 2.0)[0];
+    assert!((grey - 128.0 / 255.0).abs() < 0.0001);
+    let between = texture.sample(center, TextureFilter::Trilinear, 0.5)[0];
+    assert!((between - 64.0 / 255.0).abs() < 0.0001);
+    assert_eq!(texture.sample(center, TextureFilter::Trilinear, 10.0), texture.sample(center, TextureFilter::Trilinear, 2.0));
+}
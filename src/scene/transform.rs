@@ -0,0 +1,86 @@
+extern crate nalgebra;
+use nalgebra::{Matrix4, UnitQuaternion, Vector3};
+
+use super::mesh::Vert3;
+use crate::transformations::make_translation_matrix;
+
+// where an object sits in its parent's space, kept apart from the mesh so the mesh verts are never rewritten
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Transform {
+    pub translation:Vert3,
+    pub rotation:UnitQuaternion<f32>, // rotates object space axes into the parent's space
+    pub scale:Vert3
+}
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            translation: Vert3{x:0.0, y:0.0, z:0.0},
+            rotation: UnitQuaternion::identity(),
+            scale: Vert3{x:1.0, y:1.0, z:1.0}
+        }
+    }
+    pub fn from_translation(x:f32, y:f32, z:f32) -> Self {
+        Self { translation: Vert3{x, y, z}, ..Self::identity() }
+    }
+
+    pub fn set_uniform_scale(&mut self, scale:f32) {
+        self.scale = Vert3{x:scale, y:scale, z:scale};
+    }
+
+    // same angles as make_x/y/z_rotation_matrix, rotating around y first, then x, then z
+    pub fn set_rotation_degrees(&mut self, x_degrees:f32, y_degrees:f32, z_degrees:f32) {
+        let x_rot = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -x_degrees.to_radians());
+        let y_rot = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), y_degrees.to_radians());
+        let z_rot = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), -z_degrees.to_radians());
+        self.rotation = z_rot * x_rot * y_rot;
+    }
+
+    // turns the +z axis to point along direction, which is the way cameras look and directional lights shine
+    // rotation is left alone if direction has no length
+    pub fn set_direction(&mut self, direction:Vert3) {
+        let direction = Vector3::new(direction.x, direction.y, direction.z);
+        if direction.norm_squared() > 0.0 {
+            // rotation_between has no single answer for straight backwards, any half turn will do
+            self.rotation = UnitQuaternion::rotation_between(&Vector3::z(), &direction)
+                .unwrap_or_else(|| UnitQuaternion::from_axis_angle(&Vector3::y_axis(), std::f32::consts::PI));
+        }
+    }
+
+    // scale, then rotation, then translation, for row vectors (vert * matx) like the other transforms
+    pub fn matrix(&self) -> Matrix4<f32> {
+        let scale = Matrix4::new_nonuniform_scaling(&Vector3::new(self.scale.x, self.scale.y, self.scale.z));
+        // the quaternion's matrix is for column vectors, its transpose does the same to row vectors
+        let rotation = self.rotation.to_homogeneous().transpose();
+        scale * rotation * make_translation_matrix(self.translation.x, self.translation.y, self.translation.z)
+    }
+}
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+#[test]
+fn transform_matrix_test() {
+    use crate::transformations::*;
+    let close = |a:Vert3, b:Vert3| (a.x - b.x).abs() < 0.0001 && (a.y - b.y).abs() < 0.0001 && (a.z - b.z).abs() < 0.0001;
+
+    let mut transform = Transform::from_translation(1.0, -2.0, 3.0);
+    transform.set_rotation_degrees(30.0, 45.0, 60.0);
+    transform.scale = Vert3{x:2.0, y:3.0, z:4.0};
+
+    // same result as composing the matrices one at a time in the same order
+    let composed = Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 3.0, 4.0))
+        * make_y_rotation_matrix(45.0)
+        * make_x_rotation_matrix(30.0)
+        * make_z_rotation_matrix(60.0)
+        * make_translation_matrix(1.0, -2.0, 3.0);
+    let pt = Vert3{x:0.5, y:-1.5, z:2.5};
+    assert!(close(pt.transformed(&transform.matrix()), pt.transformed(&composed)));
+
+    // identity leaves points alone, scale applies before translation
+    assert!(close(pt.transformed(&Transform::identity().matrix()), pt));
+    let mut scaled = Transform::from_translation(10.0, 0.0, 0.0);
+    scaled.set_uniform_scale(2.0);
+    assert!(close(Vert3{x:1.0, y:1.0, z:1.0}.transformed(&scaled.matrix()), Vert3{x:12.0, y:2.0, z:2.0}));
+}
@@ -1,5 +1,5 @@
 extern crate nalgebra as na;
-use na::{Matrix4};
+use na::{Matrix3, Matrix4};
 
 pub fn make_x_rotation_matrix(angle_degrees:f32) -> Matrix4<f32>{
     let angle_radians = angle_degrees.to_radians();
@@ -17,7 +17,7 @@ pub fn make_y_rotation_matrix(angle_degrees:f32) -> Matrix4<f32>{
         angle_radians.cos(), 0.0, -1.0 * angle_radians.sin(), 0.0,
                 0.0        , 1.0,            0.0            , 0.0,
         angle_radians.sin(), 0.0,    angle_radians.cos()    , 0.0,
-                0.0        , 0.0,            0.0            , 0.0
+                0.0        , 0.0,            0.0            , 1.0
     );
     y_rot_matrix
 }
@@ -28,7 +28,7 @@ pub fn make_z_rotation_matrix(angle_degrees:f32) -> Matrix4<f32>{
         angle_radians.cos(), -1.0 * angle_radians.sin(), 0.0, 0.0,
         angle_radians.sin(),     angle_radians.cos()   , 0.0, 0.0,
                 0.0        ,             0.0           , 1.0, 0.0,
-                0.0        ,             0.0           , 0.0, 0.0
+                0.0        ,             0.0           , 0.0, 1.0
     );
     z_rot_matrix
 }
@@ -38,7 +38,17 @@ pub fn make_translation_matrix(x_delta:f32, y_delta:f32, z_delta:f32) -> Matrix4
           1.0  ,   0.0  ,   0.0  , 0.0,
           0.0  ,   1.0  ,   0.0  , 0.0,
           0.0  ,   0.0  ,   1.0  , 0.0,
-        x_delta, y_delta, z_delta, 0.0
+        x_delta, y_delta, z_delta, 1.0
     );
     translate_matrix
-}
\ No newline at end of file
+}
+
+// turns normals the way transfm turns the surfaces they belong to, for row vectors (normal * matx) like the other transforms
+// normals need the inverse transpose to stay perpendicular under non uniform scale, the cofactor matrix is
+// that times the determinant so it also copes with transforms that flatten everything, the result needs normalizing
+pub fn make_normal_matrix(transfm:&Matrix4<f32>) -> Matrix3<f32>{
+    let linear = transfm.fixed_slice::<3, 3>(0, 0);
+    let (row_0, row_1, row_2) = (linear.row(0), linear.row(1), linear.row(2));
+    let sign = if linear.determinant() < 0.0 { -1.0 } else { 1.0 };
+    Matrix3::from_rows(&[row_1.cross(&row_2) * sign, row_2.cross(&row_0) * sign, row_0.cross(&row_1) * sign])
+}